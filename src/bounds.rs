@@ -0,0 +1,90 @@
+use core::cmp::Ordering;
+use core::ops::{Add, Mul};
+
+#[cfg(any(feature = "std", feature = "libm"))]
+use crate::math;
+
+/// A width and height.
+///
+/// Bounds are ordered by area, then by width, then by height. That's an
+/// ordering by size, not by containment.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bounds(pub usize, pub usize);
+
+/// Return `w * h`, or `None` if the product overflows `usize`.
+pub fn checked_area(w: usize, h: usize) -> Option<usize> {
+    w.checked_mul(h)
+}
+
+impl Bounds {
+    /// Return the area `width * height`, or `None` if it overflows `usize`.
+    pub fn area(&self) -> Option<usize> {
+        checked_area(self.0, self.1)
+    }
+
+    /// Return the ratio of width to height. A zero height yields
+    /// `f64::INFINITY`.
+    pub fn aspect_ratio(&self) -> f64 {
+        if self.1 == 0 {
+            return f64::INFINITY;
+        }
+        self.0 as f64 / self.1 as f64
+    }
+
+    /// Return true if `(x, y)` lies within `[0, width) x [0, height)`.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.0 && y < self.1
+    }
+
+    /// Return these bounds with each dimension multiplied by `factor` and
+    /// rounded to the nearest integer.
+    #[cfg(any(feature = "std", feature = "libm"))]
+    pub fn scale(&self, factor: f64) -> Bounds {
+        Bounds(math::round(self.0 as f64 * factor) as usize,
+               math::round(self.1 as f64 * factor) as usize)
+    }
+}
+
+impl Mul<usize> for Bounds {
+    type Output = Bounds;
+
+    fn mul(self, factor: usize) -> Bounds {
+        Bounds(self.0.saturating_mul(factor), self.1.saturating_mul(factor))
+    }
+}
+
+impl Add for Bounds {
+    type Output = Bounds;
+
+    fn add(self, other: Bounds) -> Bounds {
+        Bounds(self.0.saturating_add(other.0), self.1.saturating_add(other.1))
+    }
+}
+
+impl From<(usize, usize)> for Bounds {
+    fn from((width, height): (usize, usize)) -> Bounds {
+        Bounds(width, height)
+    }
+}
+
+impl From<Bounds> for (usize, usize) {
+    fn from(bounds: Bounds) -> (usize, usize) {
+        (bounds.0, bounds.1)
+    }
+}
+
+impl Ord for Bounds {
+    fn cmp(&self, other: &Bounds) -> Ordering {
+        let area = |b: &Bounds| b.0 as u128 * b.1 as u128;
+        area(self).cmp(&area(other))
+            .then(self.0.cmp(&other.0))
+            .then(self.1.cmp(&other.1))
+    }
+}
+
+impl PartialOrd for Bounds {
+    fn partial_cmp(&self, other: &Bounds) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
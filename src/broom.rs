@@ -0,0 +1,64 @@
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "alloc")]
+use core::convert::TryFrom;
+#[cfg(feature = "alloc")]
+use core::str::FromStr;
+
+/// Two possible alternative for what a broom could be working on
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BroomIntent {
+    #[default]
+    FetchWater,
+    DumpWater
+}
+
+impl fmt::Display for BroomIntent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BroomIntent::FetchWater => write!(f, "fetching water"),
+            BroomIntent::DumpWater => write!(f, "dumping water")
+        }
+    }
+}
+
+/// The error returned when a string doesn't name a `BroomIntent`.
+#[cfg(feature = "alloc")]
+#[derive(Debug, PartialEq)]
+pub struct InvalidIntent(pub String);
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for InvalidIntent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown broom intent {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidIntent {}
+
+/// Accepts, ignoring case, `"fetch"`, `"fetch_water"`, and `"fetching water"`,
+/// and the corresponding names for `DumpWater`, so `Display`'s output parses
+/// back to the same intent.
+#[cfg(feature = "alloc")]
+impl FromStr for BroomIntent {
+    type Err = InvalidIntent;
+    fn from_str(s: &str) -> Result<BroomIntent, InvalidIntent> {
+        match s.to_ascii_lowercase().as_str() {
+            "fetch" | "fetch_water" | "fetching water" => Ok(BroomIntent::FetchWater),
+            "dump" | "dump_water" | "dumping water" => Ok(BroomIntent::DumpWater),
+            _ => Err(InvalidIntent(s.to_string()))
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TryFrom<&str> for BroomIntent {
+    type Error = InvalidIntent;
+    fn try_from(s: &str) -> Result<BroomIntent, InvalidIntent> {
+        s.parse()
+    }
+}
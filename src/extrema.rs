@@ -0,0 +1,53 @@
+/// References to the greatest and least elements of some slice.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Extrema<'elt, T> {
+    pub greatest: &'elt T,
+    pub least: &'elt T
+}
+
+/// Return the greatest and least elements of `slice`, or `None` if it's
+/// empty. Of several equal extremes, the first is returned.
+pub fn find_extrema<'s, T: Ord>(slice: &'s [T]) -> Option<Extrema<'s, T>> {
+    let (first, rest) = slice.split_first()?;
+    let mut greatest = first;
+    let mut least = first;
+
+    for x in rest {
+        if x < least    { least     = x; }
+        if x > greatest { greatest = x; }
+    }
+
+    Some(Extrema { greatest, least })
+}
+
+/// Like `find_extrema`, but also return the indices of the greatest and least
+/// elements, in that order.
+pub fn find_extrema_indexed<T: Ord>(slice: &[T]) -> Option<(Extrema<'_, T>, usize, usize)> {
+    let (first, rest) = slice.split_first()?;
+    let (mut greatest, mut greatest_index) = (first, 0);
+    let (mut least, mut least_index) = (first, 0);
+
+    for (i, x) in rest.iter().enumerate() {
+        if x < least    { least     = x; least_index     = i + 1; }
+        if x > greatest { greatest = x; greatest_index = i + 1; }
+    }
+
+    Some((Extrema { greatest, least }, greatest_index, least_index))
+}
+
+impl<'elt> Extrema<'elt, i32> {
+    /// Return `greatest - least`.
+    pub fn range(&self) -> i32 {
+        *self.greatest - *self.least
+    }
+
+    /// Return `greatest - least`, or `None` if it overflows `i32`.
+    pub fn checked_range(&self) -> Option<i32> {
+        self.greatest.checked_sub(*self.least)
+    }
+
+    /// Return true if `value` lies in `[least, greatest]`.
+    pub fn contains(&self, value: &i32) -> bool {
+        self.least <= value && value <= self.greatest
+    }
+}
@@ -0,0 +1,1227 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Index, IndexMut, Not};
+
+use crate::{checked_area, Bounds};
+
+#[cfg(feature = "std")]
+use alloc::{format, string::ToString};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+/// A rectangle of eight-bit grayscale pixels.
+#[derive(Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedGrayscaleMap"))]
+pub struct GrayscaleMap {
+    pub pixels: Vec<u8>,
+    pub size: (usize, usize)
+}
+
+impl GrayscaleMap {
+    /// Return a mask that is 255 wherever `self` and `prev` differ by more
+    /// than `threshold`, and 0 everywhere else. Return `None` if the two
+    /// maps aren't the same size.
+    pub fn motion_mask(&self, prev: &GrayscaleMap, threshold: u8) -> Option<GrayscaleMap> {
+        if self.size != prev.size {
+            return None;
+        }
+
+        let pixels = self.pixels.iter()
+            .zip(&prev.pixels)
+            .map(|(&a, &b)| if a.abs_diff(b) > threshold { 255 } else { 0 })
+            .collect();
+
+        Some(GrayscaleMap { pixels, size: self.size })
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the image as an RGBA buffer, four bytes per pixel, with
+    /// R = G = B = the gray value and A = `alpha`.
+    pub fn to_rgba(&self, alpha: u8) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.pixels.len() * 4);
+        for &gray in &self.pixels {
+            rgba.extend_from_slice(&[gray, gray, gray, alpha]);
+        }
+        rgba
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl GrayscaleMap {
+    /// Replace every pixel `p` with `f(p)`, processing the buffer in
+    /// parallel.
+    pub fn par_map_pixels<F: Fn(u8) -> u8 + Sync>(&mut self, f: F) {
+        use rayon::prelude::*;
+        self.pixels.par_iter_mut().for_each(|p| *p = f(*p));
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the `w` x `h` region centered in the image, or `None` if
+    /// it's larger than the image.
+    pub fn center_crop(&self, w: usize, h: usize) -> Option<GrayscaleMap> {
+        let (width, height) = self.size;
+        if w > width || h > height {
+            return None;
+        }
+
+        self.crop((width - w) / 2, (height - h) / 2, w, h)
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the run-length encoding of row `y` as `(value, count)`
+    /// pairs, or `None` if `y` is past the bottom of the image.
+    pub fn row_run_lengths(&self, y: usize) -> Option<Vec<(u8, usize)>> {
+        let (width, height) = self.size;
+        if y >= height {
+            return None;
+        }
+
+        let mut runs: Vec<(u8, usize)> = Vec::new();
+        for &p in &self.pixels[y * width..(y + 1) * width] {
+            match runs.last_mut() {
+                Some((value, count)) if *value == p => *count += 1,
+                _ => runs.push((p, 1))
+            }
+        }
+
+        Some(runs)
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the multiply blend of `self` and `other`, or `None` if the
+    /// two maps aren't the same size.
+    pub fn multiply_image(&self, other: &GrayscaleMap) -> Option<GrayscaleMap> {
+        if self.size != other.size {
+            return None;
+        }
+
+        let pixels = self.pixels.iter()
+            .zip(&other.pixels)
+            .map(|(&a, &b)| (a as u16 * b as u16 / 255) as u8)
+            .collect();
+
+        Some(GrayscaleMap { pixels, size: self.size })
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the intensity-weighted centroid `(x, y)` of the pixels
+    /// brighter than `threshold`, or `None` if there are none.
+    pub fn centroid(&self, threshold: u8) -> Option<(f64, f64)> {
+        let width = self.size.0;
+        let (mut sum_x, mut sum_y, mut total) = (0.0, 0.0, 0.0);
+
+        for (i, &p) in self.pixels.iter().enumerate() {
+            if p > threshold {
+                let w = p as f64;
+                sum_x += (i % width) as f64 * w;
+                sum_y += (i / width) as f64 * w;
+                total += w;
+            }
+        }
+
+        if total == 0.0 {
+            None
+        } else {
+            Some((sum_x / total, sum_y / total))
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl GrayscaleMap {
+    /// Return a copy of the image with contrast-limited adaptive histogram
+    /// equalization applied over `tile_size` x `tile_size` tiles. A
+    /// `tile_size` of zero returns the image unchanged.
+    pub fn clahe(&self, tile_size: usize, clip_limit: f64) -> GrayscaleMap {
+        let (width, height) = self.size;
+        if tile_size == 0 || self.pixels.is_empty() {
+            return self.clone();
+        }
+
+        let tiles_x = width.div_ceil(tile_size);
+        let tiles_y = height.div_ceil(tile_size);
+
+        // Build a lookup table for each tile, in row-major tile order.
+        let mut luts = Vec::with_capacity(tiles_x * tiles_y);
+        for ty in 0..tiles_y {
+            for tx in 0..tiles_x {
+                let mut histogram = [0u32; 256];
+                let (x0, y0) = (tx * tile_size, ty * tile_size);
+                let (x1, y1) = ((x0 + tile_size).min(width), (y0 + tile_size).min(height));
+                for y in y0..y1 {
+                    for &p in &self.pixels[y * width + x0..y * width + x1] {
+                        histogram[p as usize] += 1;
+                    }
+                }
+                let count = ((x1 - x0) * (y1 - y0)) as u32;
+
+                let limit = (clip_limit * count as f64 / 256.0).max(1.0) as u32;
+                let mut excess = 0;
+                for bin in histogram.iter_mut() {
+                    if *bin > limit {
+                        excess += *bin - limit;
+                        *bin = limit;
+                    }
+                }
+                for (i, bin) in histogram.iter_mut().enumerate() {
+                    *bin += excess / 256 + if (i as u32) < excess % 256 { 1 } else { 0 };
+                }
+
+                let mut lut = [0u8; 256];
+                let mut cdf = 0;
+                for (value, &bin) in histogram.iter().enumerate() {
+                    cdf += bin;
+                    lut[value] = (cdf as f64 * 255.0 / count as f64).round() as u8;
+                }
+                luts.push(lut);
+            }
+        }
+
+        // Find the two tile centers on either side of `pos` along an axis
+        // with `tiles` tiles, and how far `pos` is from the first.
+        let neighbors = |pos: usize, tiles: usize| {
+            let f = (pos as f64 + 0.5) / tile_size as f64 - 0.5;
+            let lo = (f.floor().max(0.0) as usize).min(tiles - 1);
+            let hi = (lo + 1).min(tiles - 1);
+            (lo, hi, (f - lo as f64).clamp(0.0, 1.0))
+        };
+
+        let mut pixels = Vec::with_capacity(self.pixels.len());
+        for y in 0..height {
+            let (ty0, ty1, wy) = neighbors(y, tiles_y);
+            for x in 0..width {
+                let (tx0, tx1, wx) = neighbors(x, tiles_x);
+                let p = self.pixels[y * width + x] as usize;
+                let map = |tx: usize, ty: usize| luts[ty * tiles_x + tx][p] as f64;
+
+                let top = map(tx0, ty0) * (1.0 - wx) + map(tx1, ty0) * wx;
+                let bottom = map(tx0, ty1) * (1.0 - wx) + map(tx1, ty1) * wx;
+                pixels.push((top * (1.0 - wy) + bottom * wy).round() as u8);
+            }
+        }
+
+        GrayscaleMap { pixels, size: self.size }
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let group = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(BASE64_ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+pub fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=').as_bytes();
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut group = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            let digit = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+            group |= digit << (18 - 6 * i);
+        }
+        bytes.extend_from_slice(&group.to_be_bytes()[1..chunk.len()]);
+    }
+    Some(bytes)
+}
+
+#[cfg(feature = "std")]
+impl GrayscaleMap {
+    /// Return the image as a binary PGM file, base64-encoded.
+    pub fn to_base64_pgm(&self) -> String {
+        let mut pgm = Vec::new();
+        self.write_pgm(&mut pgm).expect("writing to a Vec can't fail");
+        base64_encode(&pgm)
+    }
+
+    /// Decode an image produced by `to_base64_pgm`. Return `None` if `text`
+    /// isn't valid base64, or doesn't hold exactly one well-formed 8-bit
+    /// PGM image with nothing after it.
+    pub fn from_base64_pgm(text: &str) -> Option<GrayscaleMap> {
+        let pgm = base64_decode(text)?;
+        let mut rest = &pgm[..];
+        let map = GrayscaleMap::read_pgm(&mut rest).ok()?;
+        if !rest.is_empty() {
+            return None;
+        }
+        Some(map)
+    }
+}
+
+impl GrayscaleMap {
+    /// Return a copy of the image scaled to `width` x `height` using
+    /// nearest-neighbor sampling. Scaling an empty image yields black.
+    /// Panic if `width * height` overflows `usize`.
+    pub fn resize_nearest(&self, width: usize, height: usize) -> GrayscaleMap {
+        let (src_width, src_height) = self.size;
+        let area = checked_area(width, height).expect("image dimensions overflow usize");
+        if self.pixels.is_empty() {
+            return GrayscaleMap { pixels: vec![0; area], size: (width, height) };
+        }
+
+        let mut pixels = Vec::with_capacity(area);
+        for y in 0..height {
+            let row = y * src_height / height * src_width;
+            for x in 0..width {
+                pixels.push(self.pixels[row + x * src_width / width]);
+            }
+        }
+
+        GrayscaleMap { pixels, size: (width, height) }
+    }
+}
+
+impl GrayscaleMap {
+    /// Return a copy of the image scaled, preserving its aspect ratio, to
+    /// the largest size that fits within `max_width` x `max_height`. A
+    /// non-empty box always yields at least one pixel in each direction.
+    /// An image with no pixels is scaled to fill the whole box.
+    /// Panic if the scaled dimensions overflow `usize`.
+    pub fn resize_to_fit(&self, max_width: usize, max_height: usize) -> GrayscaleMap {
+        let (width, height) = self.size;
+        if width == 0 || height == 0 || max_width == 0 || max_height == 0 {
+            return self.resize_nearest(max_width, max_height);
+        }
+
+        let overflow = "image dimensions overflow usize";
+        let (width, height) = if checked_area(max_width, height).expect(overflow)
+            <= checked_area(max_height, width).expect(overflow)
+        {
+            (max_width, (height * max_width / width).max(1))
+        } else {
+            ((width * max_height / height).max(1), max_height)
+        };
+        self.resize_nearest(width, height)
+    }
+}
+
+impl GrayscaleMap {
+    /// Tile thumbnails of `maps`, each fitted within `thumb` by
+    /// `resize_to_fit` and centered in its cell, into a grid `cols`
+    /// cells wide, filling uncovered pixels with `bg`. A `cols` of zero
+    /// is treated as one.
+    /// Panic if the sheet's dimensions overflow `usize`.
+    pub fn contact_sheet(maps: &[GrayscaleMap], thumb: (usize, usize), cols: usize, bg: u8)
+        -> GrayscaleMap
+    {
+        let cols = cols.max(1);
+        let rows = maps.len().div_ceil(cols);
+        let (thumb_width, thumb_height) = thumb;
+        let overflow = "contact sheet dimensions overflow usize";
+        let width = cols.checked_mul(thumb_width).expect(overflow);
+        let height = rows.checked_mul(thumb_height).expect(overflow);
+        let mut sheet = GrayscaleMap {
+            pixels: vec![bg; checked_area(width, height).expect(overflow)],
+            size: (width, height)
+        };
+
+        for (i, map) in maps.iter().enumerate() {
+            let thumbnail = map.resize_to_fit(thumb_width, thumb_height);
+            let (fit_width, fit_height) = thumbnail.size;
+            let left = i % cols * thumb_width + (thumb_width - fit_width) / 2;
+            let top = i / cols * thumb_height + (thumb_height - fit_height) / 2;
+            for (y, row) in thumbnail.pixels.chunks(fit_width.max(1)).enumerate() {
+                let start = (top + y) * width + left;
+                sheet.pixels[start..start + row.len()].copy_from_slice(row);
+            }
+        }
+
+        sheet
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the Sobel gradient magnitude `|gx| + |gy|` of every pixel,
+    /// in the same order as `pixels`.
+    pub fn sobel_energy(&self) -> Vec<u32> {
+        let (width, height) = self.size;
+        let at = |x: isize, y: isize| {
+            let x = x.max(0).min(width as isize - 1) as usize;
+            let y = y.max(0).min(height as isize - 1) as usize;
+            self.pixels[y * width + x] as i32
+        };
+
+        let mut energy = Vec::with_capacity(self.pixels.len());
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let gx = (at(x + 1, y - 1) + 2 * at(x + 1, y) + at(x + 1, y + 1))
+                       - (at(x - 1, y - 1) + 2 * at(x - 1, y) + at(x - 1, y + 1));
+                let gy = (at(x - 1, y + 1) + 2 * at(x, y + 1) + at(x + 1, y + 1))
+                       - (at(x - 1, y - 1) + 2 * at(x, y - 1) + at(x + 1, y - 1));
+                energy.push(gx.unsigned_abs() + gy.unsigned_abs());
+            }
+        }
+        energy
+    }
+}
+
+impl GrayscaleMap {
+    /// Return a copy of the image one pixel narrower, with the vertical
+    /// seam of lowest Sobel energy removed. An image with no columns is
+    /// returned unchanged.
+    pub fn carve_seam_vertical(&self) -> GrayscaleMap {
+        let (width, height) = self.size;
+        if width == 0 {
+            return self.clone();
+        }
+
+        // The candidate predecessors of column `x` in the row above.
+        let above = |x: usize| x.saturating_sub(1)..(x + 2).min(width);
+
+        let mut cost: Vec<u64> = self.sobel_energy().into_iter().map(u64::from).collect();
+        for y in 1..height {
+            for x in 0..width {
+                let cheapest = above(x).map(|px| cost[(y - 1) * width + px]).min().unwrap();
+                cost[y * width + x] += cheapest;
+            }
+        }
+
+        // Walk back up from the bottom, recording the seam's column in
+        // each row.
+        let mut seam = vec![0; height];
+        for y in (0..height).rev() {
+            let candidates = if y == height - 1 { 0..width } else { above(seam[y + 1]) };
+            seam[y] = candidates.min_by_key(|&x| cost[y * width + x]).unwrap();
+        }
+
+        let mut pixels = Vec::with_capacity((width - 1) * height);
+        for (y, row) in self.pixels.chunks(width).enumerate() {
+            pixels.extend_from_slice(&row[..seam[y]]);
+            pixels.extend_from_slice(&row[seam[y] + 1..]);
+        }
+
+        GrayscaleMap { pixels, size: (width - 1, height) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl GrayscaleMap {
+    /// Return the `(rho, theta)` parameters of the lines formed by pixels
+    /// brighter than `threshold`, keeping those with more than
+    /// `vote_threshold` votes. Results are sorted by votes, most first.
+    pub fn hough_lines(&self, threshold: u8, vote_threshold: u32) -> Vec<(f64, f64)> {
+        use core::f64::consts::PI;
+
+        const THETA_STEPS: usize = 180;
+        let (width, height) = self.size;
+        let max_rho = (width as f64).hypot(height as f64).ceil() as usize;
+        let rho_steps = 2 * max_rho + 1;
+        let trig: Vec<(f64, f64)> = (0..THETA_STEPS)
+            .map(|t| (t as f64 * PI / THETA_STEPS as f64).sin_cos())
+            .collect();
+
+        // votes[t * rho_steps + r] counts the votes for theta step `t` and
+        // rho `r - max_rho`.
+        let mut votes = vec![0u32; THETA_STEPS * rho_steps];
+        for (i, &p) in self.pixels.iter().enumerate() {
+            if p <= threshold {
+                continue;
+            }
+            let (x, y) = ((i % width) as f64, (i / width) as f64);
+            for (t, &(sin, cos)) in trig.iter().enumerate() {
+                let rho = (x * cos + y * sin).round() as isize + max_rho as isize;
+                votes[t * rho_steps + rho as usize] += 1;
+            }
+        }
+
+        // The flat index of the cell at theta step `t` and rho index `r`,
+        // if there is one. Theta wraps around at pi, where the same line
+        // reappears with its rho negated.
+        let cell = |t: isize, r: isize| {
+            let steps = THETA_STEPS as isize;
+            let (t, r) = if t < 0 {
+                (t + steps, rho_steps as isize - 1 - r)
+            } else if t >= steps {
+                (t - steps, rho_steps as isize - 1 - r)
+            } else {
+                (t, r)
+            };
+            if r < 0 || r >= rho_steps as isize {
+                None
+            } else {
+                Some(t as usize * rho_steps + r as usize)
+            }
+        };
+
+        // A cell is a peak if no neighbor has more votes. Among neighbors
+        // with equal votes, only the first in scan order counts, so a flat
+        // run of votes yields one line rather than several.
+        let mut lines = Vec::new();
+        for t in 0..THETA_STEPS as isize {
+            for r in 0..rho_steps as isize {
+                let i = t as usize * rho_steps + r as usize;
+                let n = votes[i];
+                let is_peak = (-1..=1)
+                    .flat_map(|dt| (-1..=1).map(move |dr| (dt, dr)))
+                    .filter_map(|(dt, dr)| cell(t + dt, r + dr))
+                    .all(|j| if j < i { votes[j] < n } else { votes[j] <= n });
+                if n > vote_threshold && is_peak {
+                    let rho = r as f64 - max_rho as f64;
+                    let theta = t as f64 * PI / THETA_STEPS as f64;
+                    lines.push((n, rho, theta));
+                }
+            }
+        }
+
+        lines.sort_by_key(|&(n, _, _)| core::cmp::Reverse(n));
+        lines.into_iter().map(|(_, rho, theta)| (rho, theta)).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl GrayscaleMap {
+    /// Return the top-left position at which `template` best matches the
+    /// image, along with its normalized cross-correlation score. Return
+    /// `None` if the template is empty or larger than the image.
+    pub fn match_template(&self, template: &GrayscaleMap) -> Option<((usize, usize), f64)> {
+        let (width, height) = self.size;
+        let (t_width, t_height) = template.size;
+        if template.pixels.is_empty() || t_width > width || t_height > height {
+            return None;
+        }
+
+        let n = template.pixels.len() as f64;
+        let t_mean = template.pixels.iter().map(|&p| p as f64).sum::<f64>() / n;
+        let t_dev: Vec<f64> = template.pixels.iter().map(|&p| p as f64 - t_mean).collect();
+        let t_norm = t_dev.iter().map(|d| d * d).sum::<f64>().sqrt();
+
+        let mut best = ((0, 0), f64::NEG_INFINITY);
+        for y in 0..=height - t_height {
+            for x in 0..=width - t_width {
+                let window = |ty: usize| {
+                    let start = (y + ty) * width + x;
+                    &self.pixels[start..start + t_width]
+                };
+                let mean = (0..t_height)
+                    .flat_map(|ty| window(ty).iter())
+                    .map(|&p| p as f64)
+                    .sum::<f64>() / n;
+
+                let (mut cross, mut spread) = (0.0, 0.0);
+                let values = (0..t_height).flat_map(|ty| window(ty).iter());
+                for (&p, &t) in values.zip(&t_dev) {
+                    let d = p as f64 - mean;
+                    cross += d * t;
+                    spread += d * d;
+                }
+
+                let denominator = spread.sqrt() * t_norm;
+                let score = if denominator == 0.0 { 0.0 } else { cross / denominator };
+                if score > best.1 {
+                    best = ((x, y), score);
+                }
+            }
+        }
+
+        Some(best)
+    }
+}
+
+/// The ways a `GrayscaleMap` can fail to be constructed.
+#[derive(Debug, PartialEq)]
+pub enum GrayscaleMapError {
+    /// The pixel buffer's length doesn't match the size.
+    SizeMismatch { expected: usize, got: usize },
+    /// Multiplying the width by the height overflows `usize`.
+    DimensionOverflow
+}
+
+impl fmt::Display for GrayscaleMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GrayscaleMapError::SizeMismatch { expected, got } =>
+                write!(f, "expected {} pixels, got {}", expected, got),
+            GrayscaleMapError::DimensionOverflow =>
+                write!(f, "image dimensions overflow usize")
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GrayscaleMapError {}
+
+impl GrayscaleMap {
+    /// Create a map of the given size from `pixels`, checking that there
+    /// is exactly one pixel for every position.
+    pub fn new(size: (usize, usize), pixels: Vec<u8>) -> Result<GrayscaleMap, GrayscaleMapError> {
+        let expected = checked_area(size.0, size.1)
+            .ok_or(GrayscaleMapError::DimensionOverflow)?;
+        if pixels.len() != expected {
+            return Err(GrayscaleMapError::SizeMismatch { expected, got: pixels.len() });
+        }
+
+        Ok(GrayscaleMap { pixels, size })
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the pixel at `(x, y)`, or `None` if it's out of bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<u8> {
+        if Bounds::from(self.size).contains(x, y) {
+            Some(self.pixels[y * self.size.0 + x])
+        } else {
+            None
+        }
+    }
+
+    /// Set the pixel at `(x, y)` to `value`. Return `false`, and leave the
+    /// map unchanged, if `(x, y)` is out of bounds.
+    pub fn set(&mut self, x: usize, y: usize, value: u8) -> bool {
+        if Bounds::from(self.size).contains(x, y) {
+            self.pixels[y * self.size.0 + x] = value;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Return the pixel at `(x, y)` without bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// `x` must be less than the width and `y` less than the height.
+    pub unsafe fn get_unchecked(&self, x: usize, y: usize) -> u8 {
+        *self.pixels.get_unchecked(y * self.size.0 + x)
+    }
+
+    /// Set the pixel at `(x, y)` to `value` without bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// `x` must be less than the width and `y` less than the height.
+    pub unsafe fn set_unchecked(&mut self, x: usize, y: usize, value: u8) {
+        *self.pixels.get_unchecked_mut(y * self.size.0 + x) = value;
+    }
+}
+
+impl GrayscaleMap {
+    /// Return an iterator over the image's rows, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        // chunks_exact panics on a zero chunk size; an image with no
+        // columns has no pixels, and so yields no rows either way.
+        self.pixels.chunks_exact(self.size.0.max(1))
+    }
+
+    /// Return an iterator over mutable slices of the image's rows, top to
+    /// bottom.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        self.pixels.chunks_exact_mut(self.size.0.max(1))
+    }
+}
+
+impl GrayscaleMap {
+    /// Return a copy of the `width` x `height` window whose top-left corner
+    /// is at `(x, y)`, or `None` if the window extends past the image.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Option<GrayscaleMap> {
+        let (src_width, src_height) = self.size;
+        if x.checked_add(width)? > src_width || y.checked_add(height)? > src_height {
+            return None;
+        }
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in y..y + height {
+            let start = row * src_width + x;
+            pixels.extend_from_slice(&self.pixels[start..start + width]);
+        }
+
+        Some(GrayscaleMap { pixels, size: (width, height) })
+    }
+}
+
+impl GrayscaleMap {
+    /// Mirror the image left to right, in place.
+    pub fn flip_horizontal(&mut self) {
+        for row in self.rows_mut() {
+            row.reverse();
+        }
+    }
+
+    /// Mirror the image top to bottom, in place.
+    pub fn flip_vertical(&mut self) {
+        let (width, height) = self.size;
+        for y in 0..height / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((height - 1 - y) * width);
+            top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+        }
+    }
+}
+
+impl GrayscaleMap {
+    /// Return a copy of the image rotated a quarter turn clockwise.
+    pub fn rotate_cw(&self) -> GrayscaleMap {
+        let (width, height) = self.size;
+        let mut pixels = Vec::with_capacity(self.pixels.len());
+        for y in 0..width {
+            for x in 0..height {
+                pixels.push(self.pixels[(height - 1 - x) * width + y]);
+            }
+        }
+        GrayscaleMap { pixels, size: (height, width) }
+    }
+
+    /// Return a copy of the image rotated a quarter turn counterclockwise.
+    pub fn rotate_ccw(&self) -> GrayscaleMap {
+        let (width, height) = self.size;
+        let mut pixels = Vec::with_capacity(self.pixels.len());
+        for y in 0..width {
+            for x in 0..height {
+                pixels.push(self.pixels[x * width + (width - 1 - y)]);
+            }
+        }
+        GrayscaleMap { pixels, size: (height, width) }
+    }
+}
+
+impl GrayscaleMap {
+    /// Replace every pixel `p` with `255 - p`.
+    pub fn invert(&mut self) {
+        self.map_pixels_mut(|p| 255 - p);
+    }
+
+    /// Set every pixel below `level` to 0, and every other pixel to 255.
+    pub fn threshold(&mut self, level: u8) {
+        self.map_pixels_mut(|p| if p < level { 0 } else { 255 });
+    }
+}
+
+#[cfg(feature = "std")]
+impl GrayscaleMap {
+    /// Write the image to `w` as a binary (P5) PGM file.
+    pub fn write_pgm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "P5\n{} {}\n255\n", self.size.0, self.size.1)?;
+        w.write_all(&self.pixels)
+    }
+
+    /// Read a binary (P5) PGM image from `r`, rescaling samples to the
+    /// range `0..=255`. Return an error of kind `InvalidData` if the
+    /// header is malformed, the maximum value is greater than 255, a
+    /// sample exceeds the maximum value, or the data is truncated.
+    pub fn read_pgm<R: Read>(r: &mut R) -> io::Result<GrayscaleMap> {
+        fn invalid(message: String) -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidData, message)
+        }
+
+        fn next_byte<R: Read>(r: &mut R) -> io::Result<u8> {
+            let mut byte = [0];
+            r.read_exact(&mut byte)?;
+            Ok(byte[0])
+        }
+
+        // Read the next header field, skipping leading whitespace and
+        // comments. This consumes the single whitespace byte that ends
+        // the field.
+        fn next_field<R: Read>(r: &mut R) -> io::Result<String> {
+            let mut field = String::new();
+            loop {
+                let byte = next_byte(r)?;
+                if byte == b'#' && field.is_empty() {
+                    while next_byte(r)? != b'\n' {}
+                } else if byte.is_ascii_whitespace() {
+                    if !field.is_empty() {
+                        return Ok(field);
+                    }
+                } else if field.len() < 20 {
+                    field.push(byte as char);
+                } else {
+                    return Err(invalid("PGM header field is too long".to_string()));
+                }
+            }
+        }
+
+        fn number<R: Read>(r: &mut R, what: &str) -> io::Result<usize> {
+            let field = next_field(r)?;
+            field.parse()
+                .map_err(|_| invalid(format!("bad PGM {}: {:?}", what, field)))
+        }
+
+        let magic = next_field(r)?;
+        if magic != "P5" {
+            return Err(invalid(format!("not a binary PGM file (magic number {:?})", magic)));
+        }
+        let width = number(r, "width")?;
+        let height = number(r, "height")?;
+        let maxval = number(r, "maximum value")?;
+        if maxval == 0 || maxval > 255 {
+            return Err(invalid(format!("unsupported PGM maximum value {}", maxval)));
+        }
+
+        let len = width.checked_mul(height)
+            .ok_or_else(|| invalid(format!("PGM size {} x {} is too large", width, height)))?;
+
+        // Don't trust the header's size enough to allocate it up front: a
+        // tiny file claiming to be 100000 x 100000 would have us ask for
+        // ten gigabytes. Let the buffer grow as data actually arrives.
+        let mut pixels = Vec::new();
+        r.take(len as u64).read_to_end(&mut pixels)?;
+        if pixels.len() != len {
+            return Err(invalid(format!("PGM data is truncated: expected {} pixels, got {}",
+                                       len, pixels.len())));
+        }
+
+        // Stretch samples from 0..=maxval to 0..=255, rounding to nearest.
+        if maxval != 255 {
+            for p in &mut pixels {
+                if *p as usize > maxval {
+                    return Err(invalid(format!("PGM sample {} exceeds maximum value {}", p, maxval)));
+                }
+                *p = ((*p as usize * 255 + maxval / 2) / maxval) as u8;
+            }
+        }
+
+        Ok(GrayscaleMap { pixels, size: (width, height) })
+    }
+}
+
+impl GrayscaleMap {
+    /// Return a copy of the image blurred by averaging each pixel with its
+    /// neighbors within `radius` pixels horizontally and vertically.
+    pub fn box_blur(&self, radius: usize) -> GrayscaleMap {
+        let (width, height) = self.size;
+        if radius == 0 || width == 0 || height == 0 {
+            return self.clone();
+        }
+
+        // Blur the `len` pixels of `src` starting at `start` and spaced
+        // `step` apart, writing the results to the same positions in `dst`.
+        fn blur_line(src: &[u8], dst: &mut [u8], start: usize, step: usize, len: usize, radius: usize) {
+            let window = 2 * radius + 1;
+            let at = |i: isize| {
+                let i = i.max(0).min(len as isize - 1) as usize;
+                src[start + i * step] as usize
+            };
+
+            let radius = radius as isize;
+            let mut sum: usize = (-radius..=radius).map(at).sum();
+            for i in 0..len as isize {
+                dst[start + i as usize * step] = ((sum + window / 2) / window) as u8;
+                sum += at(i + radius + 1);
+                sum -= at(i - radius);
+            }
+        }
+
+        let mut horizontal = vec![0; self.pixels.len()];
+        for y in 0..height {
+            blur_line(&self.pixels, &mut horizontal, y * width, 1, width, radius);
+        }
+
+        let mut pixels = vec![0; self.pixels.len()];
+        for x in 0..width {
+            blur_line(&horizontal, &mut pixels, x, width, height, radius);
+        }
+
+        GrayscaleMap { pixels, size: self.size }
+    }
+}
+
+impl fmt::Debug for GrayscaleMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GrayscaleMap")
+            .field("size", &self.size)
+            .field("pixels", &format_args!("{} bytes", self.pixels.len()))
+            .finish()
+    }
+}
+
+impl GrayscaleMap {
+    /// Create a map of the given size whose pixel at `(x, y)` is `f(x, y)`.
+    /// `f` is called exactly once per pixel, in scanline order. Panic if
+    /// `size.0 * size.1` overflows `usize`.
+    pub fn from_fn(size: (usize, usize), mut f: impl FnMut(usize, usize) -> u8) -> GrayscaleMap {
+        let (width, height) = size;
+        let area = checked_area(width, height).expect("image dimensions overflow usize");
+        let mut pixels = Vec::with_capacity(area);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(f(x, y));
+            }
+        }
+        GrayscaleMap { pixels, size }
+    }
+}
+
+#[cfg(feature = "std")]
+impl GrayscaleMap {
+    /// Return the linear interpolation between `self` and `other`, with
+    /// `alpha` clamped to `[0.0, 1.0]`. Return `None` if the two maps
+    /// aren't the same size.
+    pub fn blend(&self, other: &GrayscaleMap, alpha: f32) -> Option<GrayscaleMap> {
+        if self.size != other.size {
+            return None;
+        }
+
+        let alpha = alpha.clamp(0.0, 1.0);
+        let pixels = self.pixels.iter()
+            .zip(&other.pixels)
+            .map(|(&a, &b)| (a as f32 * (1.0 - alpha) + b as f32 * alpha).round() as u8)
+            .collect();
+
+        Some(GrayscaleMap { pixels, size: self.size })
+    }
+}
+
+impl GrayscaleMap {
+    /// Set every pixel of the `w` x `h` rectangle whose top-left corner is
+    /// at `(x, y)` to `value`, ignoring any part outside the image.
+    pub fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, value: u8) {
+        let (width, height) = self.size;
+        let x_end = x.saturating_add(w).min(width);
+        let y_end = y.saturating_add(h).min(height);
+
+        for row in y..y_end {
+            for p in &mut self.pixels[row * width + x.min(x_end)..row * width + x_end] {
+                *p = value;
+            }
+        }
+    }
+
+    /// Set the pixels along the edges of the `w` x `h` rectangle whose
+    /// top-left corner is at `(x, y)` to `value`, ignoring any part
+    /// outside the image.
+    pub fn draw_rect(&mut self, x: usize, y: usize, w: usize, h: usize, value: u8) {
+        if w == 0 || h == 0 {
+            return;
+        }
+
+        let right = x.saturating_add(w - 1);
+        let bottom = y.saturating_add(h - 1);
+        self.fill_rect(x, y, w, 1, value);
+        self.fill_rect(x, bottom, w, 1, value);
+        self.fill_rect(x, y, 1, h, value);
+        self.fill_rect(right, y, 1, h, value);
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the image convolved with the 3 x 3 `kernel`, given in
+    /// row-major order. Each output pixel is `sum / divisor + offset`,
+    /// clamped to `[0, 255]`. Panic if `divisor` is zero.
+    pub fn convolve3x3(&self, kernel: &[i32; 9], divisor: i32, offset: i32) -> GrayscaleMap {
+        assert!(divisor != 0, "convolution divisor must not be zero");
+        let (width, height) = self.size;
+        let at = |x: isize, y: isize| {
+            let x = x.max(0).min(width as isize - 1) as usize;
+            let y = y.max(0).min(height as isize - 1) as usize;
+            self.pixels[y * width + x] as i32
+        };
+
+        GrayscaleMap::from_fn(self.size, |x, y| {
+            let mut sum = 0;
+            for (i, &weight) in kernel.iter().enumerate() {
+                let dx = (i % 3) as isize - 1;
+                let dy = (i / 3) as isize - 1;
+                sum += weight * at(x as isize + dx, y as isize + dy);
+            }
+            (sum / divisor + offset).clamp(0, 255) as u8
+        })
+    }
+}
+
+impl GrayscaleMap {
+    /// Return an iterator over `(x, y, value)` for every pixel, in
+    /// scanline order.
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item = (usize, usize, u8)> + '_ {
+        let width = self.size.0.max(1);
+        self.pixels.iter().enumerate().map(move |(i, &p)| (i % width, i / width, p))
+    }
+
+    /// Return an iterator over `(x, y, &mut value)` for every pixel, in
+    /// scanline order.
+    pub fn enumerate_pixels_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut u8)> {
+        let width = self.size.0.max(1);
+        self.pixels.iter_mut().enumerate().map(move |(i, p)| (i % width, i / width, p))
+    }
+}
+
+#[cfg(feature = "std")]
+impl GrayscaleMap {
+    /// Replace every pixel `p` with `lut[p]`.
+    pub fn apply_lut(&mut self, lut: &[u8; 256]) {
+        for p in &mut self.pixels {
+            *p = lut[*p as usize];
+        }
+    }
+
+    /// Add `delta` to every pixel, saturating at 0 and 255.
+    pub fn adjust_brightness(&mut self, delta: i32) {
+        let mut lut = [0; 256];
+        for (v, entry) in lut.iter_mut().enumerate() {
+            *entry = (v as i32).saturating_add(delta).clamp(0, 255) as u8;
+        }
+        self.apply_lut(&lut);
+    }
+
+    /// Scale every pixel's distance from 128 by `factor`, saturating at 0
+    /// and 255.
+    pub fn adjust_contrast(&mut self, factor: f64) {
+        let mut lut = [0; 256];
+        for (v, entry) in lut.iter_mut().enumerate() {
+            *entry = (128.0 + (v as f64 - 128.0) * factor).round().clamp(0.0, 255.0) as u8;
+        }
+        self.apply_lut(&lut);
+    }
+
+    /// Replace every pixel `p` with `255 * (p / 255)^g`.
+    pub fn gamma(&mut self, g: f64) {
+        let mut lut = [0; 256];
+        for (v, entry) in lut.iter_mut().enumerate() {
+            *entry = (255.0 * (v as f64 / 255.0).powf(g)).round().clamp(0.0, 255.0) as u8;
+        }
+        self.apply_lut(&lut);
+    }
+}
+
+impl Index<(usize, usize)> for GrayscaleMap {
+    type Output = u8;
+    fn index(&self, (x, y): (usize, usize)) -> &u8 {
+        assert!(x < self.size.0 && y < self.size.1,
+                "pixel ({}, {}) out of range for {}x{} image", x, y, self.size.0, self.size.1);
+        &self.pixels[y * self.size.0 + x]
+    }
+}
+
+impl IndexMut<(usize, usize)> for GrayscaleMap {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut u8 {
+        assert!(x < self.size.0 && y < self.size.1,
+                "pixel ({}, {}) out of range for {}x{} image", x, y, self.size.0, self.size.1);
+        &mut self.pixels[y * self.size.0 + x]
+    }
+}
+
+impl Not for GrayscaleMap {
+    type Output = GrayscaleMap;
+    fn not(mut self) -> GrayscaleMap {
+        self.invert();
+        self
+    }
+}
+
+impl Not for &GrayscaleMap {
+    type Output = GrayscaleMap;
+    fn not(self) -> GrayscaleMap {
+        !self.clone()
+    }
+}
+
+impl GrayscaleMap {
+    /// Return a copy of the image with rows and columns exchanged.
+    pub fn transpose(&self) -> GrayscaleMap {
+        let (width, height) = self.size;
+        GrayscaleMap::from_fn((height, width), |x, y| self.pixels[x * width + y])
+    }
+}
+
+#[cfg(feature = "std")]
+impl GrayscaleMap {
+    /// Return the image as an RGB buffer, three bytes per pixel, with
+    /// R = G = B = the gray value.
+    pub fn to_rgb(&self) -> Vec<u8> {
+        let mut rgb = Vec::with_capacity(self.pixels.len() * 3);
+        for &gray in &self.pixels {
+            rgb.extend_from_slice(&[gray, gray, gray]);
+        }
+        rgb
+    }
+
+    /// Create a map of the given size from an RGB buffer, three bytes per
+    /// pixel, using Rec. 601 luma weights. Panic if `rgb` doesn't hold
+    /// exactly `size.0 * size.1` pixels.
+    pub fn from_rgb_luminance(size: (usize, usize), rgb: &[u8]) -> GrayscaleMap {
+        let area = checked_area(size.0, size.1).expect("image dimensions overflow usize");
+        assert_eq!(rgb.len(), area * 3);
+        let pixels = rgb.chunks_exact(3)
+            .map(|p| (0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64).round() as u8)
+            .collect();
+        GrayscaleMap { pixels, size }
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the image followed by successive halvings of it, each pixel
+    /// the average of a 2 x 2 block of the previous level, until the width
+    /// or height reaches 1. Odd rows and columns are dropped when halving.
+    pub fn pyramid(&self) -> Vec<GrayscaleMap> {
+        let mut levels = vec![self.clone()];
+        loop {
+            let prev = levels.last().unwrap();
+            let (width, height) = prev.size;
+            if width <= 1 || height <= 1 {
+                return levels;
+            }
+
+            let at = |x: usize, y: usize| prev.pixels[y * width + x] as u32;
+            let next = GrayscaleMap::from_fn((width / 2, height / 2), |x, y| {
+                let sum = at(2 * x, 2 * y) + at(2 * x + 1, 2 * y)
+                        + at(2 * x, 2 * y + 1) + at(2 * x + 1, 2 * y + 1);
+                ((sum + 2) / 4) as u8
+            });
+            levels.push(next);
+        }
+    }
+}
+
+impl GrayscaleMap {
+    /// Copy `src` onto `self` with its top-left corner at
+    /// `(dest_x, dest_y)`, ignoring any part that falls outside `self`.
+    pub fn composite(&mut self, src: &GrayscaleMap, dest_x: usize, dest_y: usize) {
+        self.composite_with(src, dest_x, dest_y, |_, s| s);
+    }
+
+    /// Like `composite`, but keep the brighter of each pair of pixels.
+    pub fn composite_max(&mut self, src: &GrayscaleMap, dest_x: usize, dest_y: usize) {
+        self.composite_with(src, dest_x, dest_y, |d, s| d.max(s));
+    }
+
+    fn composite_with(&mut self, src: &GrayscaleMap, dest_x: usize, dest_y: usize,
+                      combine: impl Fn(u8, u8) -> u8) {
+        let (width, height) = self.size;
+        let x_end = dest_x.saturating_add(src.size.0).min(width);
+        let y_end = dest_y.saturating_add(src.size.1).min(height);
+
+        for y in dest_y..y_end {
+            for x in dest_x..x_end {
+                let s = src.pixels[(y - dest_y) * src.size.0 + (x - dest_x)];
+                let d = &mut self.pixels[y * width + x];
+                *d = combine(*d, s);
+            }
+        }
+    }
+}
+
+impl GrayscaleMap {
+    /// Return the pixels as `(value, run_length)` pairs, in scanline order.
+    pub fn rle_encode(&self) -> Vec<(u8, u32)> {
+        let mut runs: Vec<(u8, u32)> = Vec::new();
+        for &p in &self.pixels {
+            match runs.last_mut() {
+                Some((value, len)) if *value == p && *len < u32::MAX => *len += 1,
+                _ => runs.push((p, 1))
+            }
+        }
+        runs
+    }
+
+    /// Rebuild a map of the given size from `(value, run_length)` pairs.
+    /// Return `None` if the runs don't add up to exactly `size.0 * size.1`
+    /// pixels.
+    pub fn rle_decode(size: (usize, usize), runs: &[(u8, u32)]) -> Option<GrayscaleMap> {
+        let area = checked_area(size.0, size.1)?;
+        let total = runs.iter().try_fold(0usize, |total, &(_, len)| total.checked_add(len as usize))?;
+        if total != area {
+            return None;
+        }
+
+        let mut pixels = Vec::with_capacity(area);
+        for &(value, len) in runs {
+            pixels.resize(pixels.len() + len as usize, value);
+        }
+        Some(GrayscaleMap { pixels, size })
+    }
+}
+
+impl GrayscaleMap {
+    /// Return a copy of the image with every pixel `p` replaced by `f(p)`.
+    pub fn map_pixels(&self, f: impl Fn(u8) -> u8) -> GrayscaleMap {
+        GrayscaleMap { pixels: self.pixels.iter().map(|&p| f(p)).collect(), size: self.size }
+    }
+
+    /// Replace every pixel `p` with `f(p)`, in scanline order.
+    pub fn map_pixels_mut(&mut self, mut f: impl FnMut(u8) -> u8) {
+        for p in &mut self.pixels {
+            *p = f(*p);
+        }
+    }
+}
+
+impl GrayscaleMap {
+    /// Return an iterator over every `kw` x `kh` block of pixels that fits
+    /// inside the image, in scanline order. Panic if `kw` or `kh` is zero.
+    pub fn windows(&self, kw: usize, kh: usize) -> impl Iterator<Item = Vec<u8>> + '_ {
+        assert!(kw > 0 && kh > 0, "window size must be non-zero");
+        let (width, height) = self.size;
+        let across = (width + 1).saturating_sub(kw);
+        let down = (height + 1).saturating_sub(kh);
+
+        (0..down).flat_map(move |top| (0..across).map(move |left| {
+            let mut window = Vec::with_capacity(kw * kh);
+            for row in self.rows().skip(top).take(kh) {
+                window.extend_from_slice(&row[left..left + kw]);
+            }
+            window
+        }))
+    }
+}
+
+#[cfg(feature = "std")]
+impl GrayscaleMap {
+    /// Return the number of pixels at each gray level.
+    pub fn histogram(&self) -> [usize; 256] {
+        let mut histogram = [0; 256];
+        for &p in &self.pixels {
+            histogram[p as usize] += 1;
+        }
+        histogram
+    }
+
+    /// Remap the pixels so that their gray levels are spread as evenly as
+    /// possible over `[0, 255]`. An image with fewer than two distinct
+    /// levels is left unchanged.
+    pub fn equalize(&mut self) {
+        let mut cdf = [0; 256];
+        let mut running = 0;
+        for (c, &count) in cdf.iter_mut().zip(self.histogram().iter()) {
+            running += count;
+            *c = running;
+        }
+
+        let total = self.pixels.len();
+        let cdf_min = cdf.iter().copied().find(|&c| c > 0).unwrap_or(0);
+        if total == cdf_min {
+            return;
+        }
+
+        let mut lut = [0; 256];
+        for (entry, &c) in lut.iter_mut().zip(cdf.iter()) {
+            let scaled = c.saturating_sub(cdf_min) as f64 / (total - cdf_min) as f64;
+            *entry = (scaled * 255.0).round() as u8;
+        }
+        self.apply_lut(&lut);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedGrayscaleMap {
+    pixels: Vec<u8>,
+    size: (usize, usize)
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedGrayscaleMap> for GrayscaleMap {
+    type Error = GrayscaleMapError;
+    fn try_from(raw: UncheckedGrayscaleMap) -> Result<GrayscaleMap, GrayscaleMapError> {
+        GrayscaleMap::new(raw.size, raw.pixels)
+    }
+}
@@ -0,0 +1,102 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{checked_area, Bounds, GrayscaleMap};
+
+/// A rectangle of `T` values, in row-major order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    data: Vec<T>,
+    size: (usize, usize)
+}
+
+/// The ways a `Grid` can fail to be constructed.
+#[derive(Debug, PartialEq)]
+pub enum GridError {
+    /// The data's length doesn't match the size.
+    SizeMismatch { expected: usize, got: usize },
+    /// Multiplying the width by the height overflows `usize`.
+    DimensionOverflow
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::SizeMismatch { expected, got } =>
+                write!(f, "expected {} elements, got {}", expected, got),
+            GridError::DimensionOverflow =>
+                write!(f, "grid dimensions overflow usize")
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GridError {}
+
+impl<T> Grid<T> {
+    /// Create a grid of the given size from `data`, in row-major order.
+    /// Return an error if `data` doesn't have exactly `size.0 * size.1`
+    /// elements.
+    pub fn new(size: (usize, usize), data: Vec<T>) -> Result<Grid<T>, GridError> {
+        let expected = checked_area(size.0, size.1)
+            .ok_or(GridError::DimensionOverflow)?;
+        if data.len() != expected {
+            return Err(GridError::SizeMismatch { expected, got: data.len() });
+        }
+
+        Ok(Grid { data, size })
+    }
+
+    /// Create a grid of the given size whose element at `(x, y)` is
+    /// `f(x, y)`. `f` is called exactly once per element, in row-major
+    /// order. Panic if `size.0 * size.1` overflows `usize`.
+    pub fn from_fn(size: (usize, usize), mut f: impl FnMut(usize, usize) -> T) -> Grid<T> {
+        let (width, height) = size;
+        let area = checked_area(width, height).expect("grid dimensions overflow usize");
+        let mut data = Vec::with_capacity(area);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(f(x, y));
+            }
+        }
+        Grid { data, size }
+    }
+
+    /// Return a reference to the element at `(x, y)`, or `None` if it's
+    /// out of bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if Bounds::from(self.size).contains(x, y) {
+            Some(&self.data[y * self.size.0 + x])
+        } else {
+            None
+        }
+    }
+
+    /// Set the element at `(x, y)` to `value`. Return `false`, and leave
+    /// the grid unchanged, if `(x, y)` is out of bounds.
+    pub fn set(&mut self, x: usize, y: usize, value: T) -> bool {
+        if Bounds::from(self.size).contains(x, y) {
+            self.data[y * self.size.0 + x] = value;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Return an iterator over the grid's rows, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.data.chunks_exact(self.size.0.max(1))
+    }
+}
+
+impl GrayscaleMap {
+    /// Convert `self` into a `Grid<u8>` with the same size and pixels.
+    pub fn into_grid(self) -> Grid<u8> {
+        Grid { data: self.pixels, size: self.size }
+    }
+
+    /// Convert a `Grid<u8>` into a map with the same size and pixels.
+    pub fn from_grid(grid: Grid<u8>) -> GrayscaleMap {
+        GrayscaleMap { pixels: grid.data, size: grid.size }
+    }
+}
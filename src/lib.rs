@@ -0,0 +1,52 @@
+//! The data types from the notes in `main.rs`, packaged as a library that
+//! other crates can depend on. The notes themselves use these definitions.
+//!
+//! The crate is `#![no_std]`. `Point`, `Point3`, `Bounds`, `BroomIntent`,
+//! `Extrema`, and `Luminance` need nothing beyond `core`. `Queue`,
+//! `GrayscaleMap`, `Grid`, and parsing a `BroomIntent` own heap buffers, so
+//! they're only available with the `alloc` feature. Point distances and
+//! trigonometry, `Point`'s `Display` impl, `Bounds::scale`, and scaling a
+//! `Luminance` need floating-point functions that `core` doesn't provide:
+//! enable `std`, or `libm` on targets without an operating system. The
+//! image-processing methods that do floating-point math or I/O, and the
+//! `std::error::Error` impls, are only compiled with `std`, which implies
+//! `alloc` and is on by default.
+
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(any(feature = "std", feature = "libm"))]
+mod math;
+
+mod bounds;
+mod broom;
+mod extrema;
+mod luminance;
+mod point;
+
+#[cfg(feature = "alloc")]
+mod grayscale;
+#[cfg(feature = "alloc")]
+mod grid;
+#[cfg(feature = "alloc")]
+mod queue;
+
+pub use bounds::{checked_area, Bounds};
+pub use broom::BroomIntent;
+pub use extrema::{find_extrema, find_extrema_indexed, Extrema};
+pub use luminance::Luminance;
+pub use point::{Point, Point3};
+
+#[cfg(feature = "alloc")]
+pub use broom::InvalidIntent;
+#[cfg(feature = "alloc")]
+pub use grayscale::{base64_decode, base64_encode, GrayscaleMap, GrayscaleMapError};
+#[cfg(feature = "alloc")]
+pub use grid::{Grid, GridError};
+#[cfg(feature = "alloc")]
+pub use queue::{IntoIter, Iter, Queue};
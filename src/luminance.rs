@@ -0,0 +1,45 @@
+use core::ops::{Add, Sub};
+#[cfg(any(feature = "std", feature = "libm"))]
+use core::ops::Mul;
+
+#[cfg(any(feature = "std", feature = "libm"))]
+use crate::math;
+#[cfg(feature = "alloc")]
+use crate::GrayscaleMap;
+
+/// A pixel brightness whose arithmetic saturates instead of wrapping.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Luminance(pub u8);
+
+impl Add<u8> for Luminance {
+    type Output = Luminance;
+    fn add(self, rhs: u8) -> Luminance {
+        Luminance(self.0.saturating_add(rhs))
+    }
+}
+
+impl Sub<u8> for Luminance {
+    type Output = Luminance;
+    fn sub(self, rhs: u8) -> Luminance {
+        Luminance(self.0.saturating_sub(rhs))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl Mul<f64> for Luminance {
+    type Output = Luminance;
+    fn mul(self, k: f64) -> Luminance {
+        // `as` saturates when converting floats to integers, and sends NaN
+        // to zero.
+        Luminance(math::round(self.0 as f64 * k) as u8)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl GrayscaleMap {
+    /// Return the pixel at `(x, y)` as a `Luminance`, or `None` if it's
+    /// outside the image.
+    pub fn get_luminance(&self, x: usize, y: usize) -> Option<Luminance> {
+        self.get(x, y).map(Luminance)
+    }
+}
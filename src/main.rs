@@ -1,5 +1,11 @@
 use std::{borrow::BorrowMut, u32};
 
+use structs::{
+    base64_decode, base64_encode, checked_area, find_extrema, find_extrema_indexed, Bounds,
+    BroomIntent, Extrema, GrayscaleMap, GrayscaleMapError, Grid, GridError, InvalidIntent,
+    Luminance, Point, Point3, Queue,
+};
+
 fn main() {
     println!("Hello, world!");
 
@@ -11,16 +17,21 @@ fn main() {
 
     // Rust has three kinds of struct types. Named-field, tuple-like, and unit-like which each differ in how we refer to their components. A named-field struct gives a name to each component, whereas a tuple-like struct identifies them by the order in which they appear. Unit-like structs have no components at all. These are not common, but more useful then you'd think.
 
+    // The types these notes build up, GrayscaleMap, Queue, Point, Bounds, and the rest, live in the library in src/lib.rs, so there's exactly one definition of each and other crates can use them too. Where the notes define a type, the definition is shown as a comment; where they add methods or trait impls, an "In src/..." comment says which file they went into. The code that runs below uses the library's versions.
+
 
 
     // Named-Field Structs
 
     // The definition of a named-field struct type looks like this:
-    /// A rectangle of eight-bit grayscale pixels.
-    struct GrayscaleMap {
-        pixels: Vec<u8>,
-        size: (usize, usize)
-    }
+    // /// A rectangle of eight-bit grayscale pixels.
+    // #[derive(Clone, Default, PartialEq, Eq)]
+    // #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    // #[cfg_attr(feature = "serde", serde(try_from = "UncheckedGrayscaleMap"))]
+    // struct GrayscaleMap {
+    //     pixels: Vec<u8>,
+    //     size: (usize, usize)
+    // }
 
     // This declares a type GrayscaleMap with two fields named pixels and size, of the given types. The convention in Rust is for all types, structs included, to have names that capitalize the first letter of each word, like GrayscaleMap, a convention called CamelCase. Fields and methods are lowercase, with words separated by underscores. This is called snake_case.
 
@@ -45,22 +56,23 @@ fn main() {
     assert_eq!(image.pixels.len(), 1024 * 576);
 
     // Like all other items, structs are private by default, visible only in the module where they're declared. We can make a struct visible outside its module by prefixing its definition with pub. The same goes for each of its fields, which are also private by default:
-    /// A rectangle of eight-bit grayscale pixels.
-    pub struct GrayscaleMap {
-        pub pixels: Vec<u8>,
-        pub size: (usize, usize)
-    }
+    // /// A rectangle of eight-bit grayscale pixels.
+    // pub struct GrayscaleMap {
+    //     pub pixels: Vec<u8>,
+    //     pub size: (usize, usize)
+    // }
 
     // Even if a struct is declared pub, its fields can be private:
-    /// A rectangle....
-    pub struct GrayscaleMap {
-        pixels: ...,
-        size: ...
-    }
+    // /// A rectangle....
+    // pub struct GrayscaleMap {
+    //     pixels: ...,
+    //     size: ...
+    // }
 
     // Other modules can use this struct and any public methods it might have, but can't access the private fields by name or use struct expressions to create new GrayscaleMap values. That is, creating a struct value requires all the struct's fields to be visible. This is why we can't write a struct expression to create a new String or Vec. These standard types are structs, but all their fields are private. To create one, we must use public methods like Vec::new().
 
     // When creating a named-field struct value, we can use another struct of the same type to supply values for fields we omit. In a struct expression, if the named fields are followed by .. EXPR, then any fields not mentioned take their values from EXPR, which must be another value of the same struct type. Suppose we have a struct representing a monster in a game:
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     struct Broom {
         name: String,
         height: u32,
@@ -69,20 +81,25 @@ fn main() {
         intent: BroomIntent
     }
 
-    /// Two possible alternative for what a `Broom` could be working on
-    #[derive(Copy, Clone)]
-    enum BroomIntent { FetchWater, DumpWater }
+    // /// Two possible alternative for what a `Broom` could be working on
+    // #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+    // #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    // enum BroomIntent { #[default] FetchWater, DumpWater }
 
     // A novice magician enchants a broom to do his work, but doesn't know how to stop it when the job is done. Chopping the broom in half with an axe just produces two brooms, each of half the size, but continuing the task with the same blind dedication as the original:
     // Receive the input Broom by value, taking ownership.
     fn chop(b: Broom) -> (Broom, Broom) {
+        // Split the height so that the two halves add back up to the
+        // original, giving `broom1` the extra unit if it's odd.
+        let short_half = b.height / 2;
+
         // Initialize `broom1` mostly from `b`, changing only `height`. Since
         // `String` is not `Copy`, `broom1` takes ownership of `b`'s name.
-        let mut broom1 = Broom { height: b.height / 2, .. b};
+        let mut broom1 = Broom { height: b.height - short_half, .. b};
 
         // Initialize `broom2` mostly from `broom1`. Since `String` is not
         // `Copy`, we must clone `name` explicitly.
-        let mut broom2 = Broom { name: broom1.name.clone(), .. broom1 };
+        let mut broom2 = Broom { name: broom1.name.clone(), height: short_half, .. broom1 };
 
         // Give each fragment a distinct name.
         broom1.name.push_str(" I");
@@ -106,13 +123,1145 @@ fn main() {
 
     assert_eq!(hokey2.name, "Hokey II");
     assert_eq!(hokey2.health, 100);
+
+
+
+    // Putting GrayscaleMap to Work
+
+    // A GrayscaleMap is just a buffer of bytes and a size, but once we have one, we'll want to do things with it. Since methods live in impl blocks (covered below), we can keep adding to GrayscaleMap's abilities as we need them.
+
+    // For simple motion detection across frames, we compare each frame with the previous one and mark every pixel whose brightness changed by more than some threshold. Taking the absolute difference and applying the threshold in the same pass saves us from building an intermediate difference image:
+    // In src/grayscale.rs: GrayscaleMap::motion_mask
+
+    // A frame that hasn't changed produces an all-black mask:
+    let frame = GrayscaleMap { pixels: vec![10, 20, 30, 40, 50, 60], size: (3, 2) };
+    let mask = frame.motion_mask(&frame, 0).unwrap();
+    assert_eq!(mask.size, (3, 2));
+    assert!(mask.pixels.iter().all(|&p| p == 0));
+
+    // If a bright region moves one pixel to the right, both where it left and where it arrived light up, but small flicker below the threshold doesn't:
+    let prev = GrayscaleMap { pixels: vec![200, 0, 0, 5, 5, 5], size: (3, 2) };
+    let next = GrayscaleMap { pixels: vec![0, 200, 0, 7, 5, 5], size: (3, 2) };
+    let mask = next.motion_mask(&prev, 10).unwrap();
+    assert_eq!(mask.pixels, vec![255, 255, 0, 0, 0, 0]);
+
+    // Frames of different sizes can't be compared:
+    let small = GrayscaleMap { pixels: vec![0; 4], size: (2, 2) };
+    assert!(frame.motion_mask(&small, 10).is_none());
+
+    // GPU and compositor APIs usually want four bytes per pixel, red, green, blue, and alpha, rather than our single gray byte. Expanding a grayscale image is just a matter of repeating each value three times and tacking on the alpha. Note that the color channels are passed through as is, not multiplied by alpha, so an opaque alpha of 255 is the usual choice:
+    // In src/grayscale.rs: GrayscaleMap::to_rgba
+
+    let map = GrayscaleMap { pixels: vec![0, 128, 255], size: (3, 1) };
+    let rgba = map.to_rgba(200);
+    assert_eq!(rgba.len(), 4 * map.pixels.len());
+    for (quad, &gray) in rgba.chunks(4).zip(&map.pixels) {
+        assert_eq!(quad, &[gray, gray, gray, 200]);
+    }
+
+    // Pixelwise operations are embarrassingly parallel: each output pixel depends only on the matching input pixel. For large images, we can hand the buffer to rayon's parallel iterators and let it split the work across threads. Since rayon is an extra dependency, this lives behind the optional rayon feature. Because the closure is shared between threads, it must be Sync, and it only gets to see one pixel at a time, so the result is exactly what a sequential loop would produce:
+    // In src/grayscale.rs: GrayscaleMap::par_map_pixels
+
+    #[cfg(feature = "rayon")]
+    {
+        let brighten = |p: u8| p.saturating_add(40);
+        let (width, height) = (1920, 1080);
+        let mut map = GrayscaleMap {
+            pixels: (0..width * height).map(|i| (i % 251) as u8).collect(),
+            size: (width, height)
+        };
+        let expected: Vec<u8> = map.pixels.iter().map(|&p| brighten(p)).collect();
+
+        map.par_map_pixels(brighten);
+        assert_eq!(map.pixels, expected);
+    }
+
+    // Models that expect fixed-size inputs often want the middle of an image. A center crop takes a w x h window whose offsets split the leftover space evenly; when the leftover is odd, integer division puts the extra pixel on the right or bottom:
+    // In src/grayscale.rs: GrayscaleMap::center_crop
+
+    // Each pixel of this 4 x 4 map holds 10 * y + x, so we can read the offsets right off the result. An even difference leaves equal margins:
+    let map = GrayscaleMap {
+        pixels: (0..4).flat_map(|y| (0..4).map(move |x| 10 * y + x)).collect(),
+        size: (4, 4)
+    };
+    let middle = map.center_crop(2, 2).unwrap();
+    assert_eq!(middle.size, (2, 2));
+    assert_eq!(middle.pixels, vec![11, 12, 21, 22]);
+
+    // With an odd difference, the offsets round down:
+    let strip = map.center_crop(3, 1).unwrap();
+    assert_eq!(strip.size, (3, 1));
+    assert_eq!(strip.pixels, vec![10, 11, 12]);
+
+    // And we can't crop more than we have:
+    assert!(map.center_crop(5, 2).is_none());
+    assert!(map.center_crop(2, 5).is_none());
+
+    // Run-length encoding replaces a run of identical bytes with a single (value, count) pair, which is the heart of many simple image codecs. Scanlines are the natural unit to experiment with:
+    // In src/grayscale.rs: GrayscaleMap::row_run_lengths
+
+    let map = GrayscaleMap {
+        pixels: vec![0, 255, 0, 255,
+                     7, 7, 7, 7],
+        size: (4, 2)
+    };
+    assert_eq!(map.row_run_lengths(0), Some(vec![(0, 1), (255, 1), (0, 1), (255, 1)]));
+    assert_eq!(map.row_run_lengths(1), Some(vec![(7, 4)]));
+    assert_eq!(map.row_run_lengths(2), None);
+
+    // Multiply blending is the standard compositing mode for shadows: treating each pixel as a fraction of full brightness, the result is the product of the two fractions. In bytes, that's (a * b) / 255, computed in a wider type so the product can't overflow. Multiplying can only ever darken:
+    // In src/grayscale.rs: GrayscaleMap::multiply_image
+
+    // White leaves an image alone, black blacks it out, and two mid grays make a darker gray:
+    let map = GrayscaleMap { pixels: vec![0, 64, 128, 255], size: (2, 2) };
+    let white = GrayscaleMap { pixels: vec![255; 4], size: (2, 2) };
+    let black = GrayscaleMap { pixels: vec![0; 4], size: (2, 2) };
+    let gray = GrayscaleMap { pixels: vec![128; 4], size: (2, 2) };
+
+    assert_eq!(map.multiply_image(&white).unwrap().pixels, map.pixels);
+    assert_eq!(map.multiply_image(&black).unwrap().pixels, vec![0; 4]);
+    assert_eq!(gray.multiply_image(&gray).unwrap().pixels, vec![64; 4]);
+    assert!(map.multiply_image(&GrayscaleMap { pixels: vec![0; 4], size: (4, 1) }).is_none());
+
+    // To track a bright object, we can take the center of mass of the pixels brighter than some threshold, weighting each one by its value. That's sum(x * w) / sum(w) horizontally, and likewise vertically:
+    // In src/grayscale.rs: GrayscaleMap::centroid
+
+    // A 3 x 3 blob centered at (5, 2), brighter in the middle, on a dim background:
+    let mut blob = GrayscaleMap { pixels: vec![20; 8 * 5], size: (8, 5) };
+    for y in 1..4 {
+        for x in 4..7 {
+            blob.pixels[y * 8 + x] = if (x, y) == (5, 2) { 250 } else { 150 };
+        }
+    }
+    let (cx, cy) = blob.centroid(100).unwrap();
+    assert!((cx - 5.0).abs() < 1e-9 && (cy - 2.0).abs() < 1e-9);
+
+    // Nothing clears the threshold in a dark image:
+    let dark = GrayscaleMap { pixels: vec![20; 16], size: (4, 4) };
+    assert_eq!(dark.centroid(100), None);
+
+    // Global histogram equalization stretches an image's brightness range as a whole, which does little for an image whose dark and bright regions each have little contrast of their own. Contrast-limited adaptive histogram equalization (CLAHE) equalizes each tile of the image separately instead. To keep it from blowing up noise in flat tiles, each tile's histogram is clipped at clip_limit times the average bin height before building its mapping, and the clipped excess is spread evenly over all the bins. Finally, to avoid visible seams, each pixel blends the mappings of the four nearest tile centers bilinearly:
+    // In src/grayscale.rs: GrayscaleMap::clahe
+
+    // Here's a 32 x 32 image whose left half is dark and whose right half is bright, each with only a narrow range of values of its own:
+    let (width, height) = (32, 32);
+    let regions = GrayscaleMap {
+        pixels: (0..height).flat_map(|y| (0..width).map(move |x| {
+            let base = if x < width / 2 { 20 } else { 200 };
+            base + ((x * 7 + y * 3) % 16) as u8
+        })).collect(),
+        size: (width, height)
+    };
+
+    // We'll measure local contrast as the spread of values in the middle of the dark region, away from where tile mappings blend across the boundary:
+    let spread = |map: &GrayscaleMap| {
+        let values: Vec<u8> = (8..24)
+            .flat_map(|y| (2..6).map(move |x| (x, y)))
+            .map(|(x, y)| map.pixels[y * width + x])
+            .collect();
+        values.iter().max().unwrap() - values.iter().min().unwrap()
+    };
+    assert_eq!(spread(&regions), 15);
+
+    // Equalizing over 8 x 8 tiles stretches the dark region's narrow range much wider:
+    let enhanced = regions.clahe(8, 40.0);
+    assert_eq!(enhanced.size, regions.size);
+    assert!(spread(&enhanced) > 4 * spread(&regions));
+
+    // But a tight clip limit keeps the amplification in check:
+    let restrained = regions.clahe(8, 1.5);
+    assert!(spread(&restrained) > spread(&regions));
+    assert!(spread(&restrained) < spread(&enhanced));
+
+    // A tile size of zero makes no sense, so we leave the image alone:
+    assert_eq!(regions.clahe(0, 40.0).pixels, regions.pixels);
+
+    // To drop an image into a log line or a JSON field, we need it as text. The binary PGM format is about the simplest self-describing encoding of a grayscale image there is: a short ASCII header, "P5", the width and height, and the maximum pixel value, followed by the raw bytes. Base64-encoding that gives us a string that carries its own size. Base64 is simple enough to write ourselves: every three bytes become four characters drawn from a 64-character alphabet, with = padding at the end:
+    // In src/grayscale.rs:
+    //     BASE64_ALPHABET, base64_encode
+    //     base64_decode
+    //     GrayscaleMap::to_base64_pgm, GrayscaleMap::from_base64_pgm
+
+    // The encoding is plain text, and round-trips exactly:
+    let map = GrayscaleMap { pixels: (0..=255).collect(), size: (32, 8) };
+    let text = map.to_base64_pgm();
+    assert!(text.starts_with("UDUKMzIgOAoyNTUK")); // "P5\n32 8\n255\n"
+    let decoded = GrayscaleMap::from_base64_pgm(&text).unwrap();
+    assert_eq!(decoded.size, map.size);
+    assert_eq!(decoded.pixels, map.pixels);
+
+    // Lengths that aren't a multiple of three get padded:
+    assert_eq!(base64_encode(b"Ma"), "TWE=");
+    assert_eq!(base64_decode("TWE="), Some(b"Ma".to_vec()));
+
+    // And text that isn't a PGM image, or isn't base64 at all, or has anything left over after the pixels, is rejected:
+    assert!(GrayscaleMap::from_base64_pgm(&base64_encode(b"P6\n1 1\n255\nxyz")).is_none());
+    assert!(GrayscaleMap::from_base64_pgm(&base64_encode(b"P5\n1 1\n255\nxy")).is_none());
+    assert!(GrayscaleMap::from_base64_pgm("not base64!").is_none());
+
+    // Scaling an image to an arbitrary size is easiest with nearest-neighbor sampling: each output pixel simply copies the source pixel its position maps back to. It's crude, but it's exact for integer scale factors and good enough for thumbnails:
+    // In src/grayscale.rs: GrayscaleMap::resize_nearest
+
+    let map = GrayscaleMap { pixels: vec![1, 2, 3, 4], size: (2, 2) };
+    assert_eq!(map.resize_nearest(4, 2).pixels, vec![1, 1, 2, 2, 3, 3, 4, 4]);
+    assert_eq!(map.resize_nearest(1, 1).pixels, vec![1]);
+
+    // Stretching an image to an arbitrary size distorts it, though. More often we want the largest image with the same proportions that still fits inside a box, so one dimension fills the box and the other comes up short:
+    // In src/grayscale.rs: GrayscaleMap::resize_to_fit
+
+    let map = GrayscaleMap { pixels: vec![1, 2, 3, 4, 5, 6], size: (3, 2) };
+    assert_eq!(map.resize_to_fit(6, 6).size, (6, 4));
+    assert_eq!(map.resize_to_fit(6, 2).size, (3, 2));
+    assert_eq!(map.resize_to_fit(1, 1).pixels, vec![1]);
+
+    // With that, we can build a contact sheet: fit a set of images, which may all be different sizes, into same-sized cells, and tile them left to right, top to bottom, in a grid cols cells wide. Each thumbnail is centered in its cell, and whatever it doesn't cover, including any cells left over in the last row, is filled with the background value. The sheet's dimensions are products of the caller's numbers, so like resize_nearest we compute them with checked arithmetic and panic rather than wrap:
+    // In src/grayscale.rs: GrayscaleMap::contact_sheet
+
+    // Three flat images of different sizes on a sheet two cells wide leaves one empty cell. The wide image only fills the top row of its cell; the others fill theirs completely:
+    let maps = [
+        GrayscaleMap { pixels: vec![10; 6], size: (3, 2) },
+        GrayscaleMap { pixels: vec![20; 25], size: (5, 5) },
+        GrayscaleMap { pixels: vec![30; 1], size: (1, 1) },
+    ];
+    let sheet = GrayscaleMap::contact_sheet(&maps, (2, 2), 2, 0);
+    assert_eq!(sheet.size, (4, 4));
+    assert_eq!(sheet.pixels, vec![10, 10, 20, 20,
+                                   0,  0, 20, 20,
+                                  30, 30,  0,  0,
+                                  30, 30,  0,  0]);
+
+    // Seam carving is a content-aware way to shrink an image: rather than squeezing everything, it removes a connected path of pixels, one per row, that runs through the least interesting part of the picture. "Interesting" is measured by an energy map, here the magnitude of the Sobel gradient, which is large at edges and zero in flat regions. The Sobel operator looks at each pixel's 3 x 3 neighborhood, so at the borders we clamp to the nearest pixel inside the image:
+    // In src/grayscale.rs: GrayscaleMap::sobel_energy
+
+    // To find the cheapest vertical seam, we use dynamic programming: the cheapest seam ending at a pixel costs that pixel's energy plus the cheapest seam ending at one of the three pixels above it. After filling in the table row by row, we start from the cheapest pixel in the bottom row and walk back up, choosing the cheapest of the three predecessors each time:
+    // In src/grayscale.rs: GrayscaleMap::carve_seam_vertical
+
+    // Here's a busy checkerboard with a flat vertical corridor through columns 2 through 4. The middle of the corridor has no energy at all, so that's the seam that goes:
+    let busy = GrayscaleMap {
+        pixels: (0..5).flat_map(|y| (0..8).map(move |x| {
+            if (2..5).contains(&x) { 100 } else if (x + y) % 2 == 0 { 0 } else { 255 }
+        })).collect(),
+        size: (8, 5)
+    };
+    assert_eq!(busy.sobel_energy()[3], 0);
+
+    let carved = busy.carve_seam_vertical();
+    assert_eq!(carved.size, (7, 5));
+    let expected: Vec<u8> = busy.pixels.chunks(8)
+        .flat_map(|row| row[..3].iter().chain(&row[4..]).cloned())
+        .collect();
+    assert_eq!(carved.pixels, expected);
+
+    // Each call takes off one more column:
+    let narrower = carved.carve_seam_vertical().carve_seam_vertical();
+    assert_eq!(narrower.size, (5, 5));
+    assert_eq!(narrower.pixels.len(), 25);
+
+    // The Hough transform finds straight lines in an edge image. Any line can be written as rho = x cos(theta) + y sin(theta), where theta is the angle of the line's normal and rho is its distance from the origin. Each edge pixel votes for every (rho, theta) pair of a line that could pass through it, and the pairs that collect the most votes are the lines actually in the image. We use one-degree steps for theta and one-pixel steps for rho. Since a line also earns a fair number of votes in neighboring cells, we only report cells that are local maxima:
+    // In src/grayscale.rs: GrayscaleMap::hough_lines
+
+    // A horizontal line 30 pixels down has its normal pointing straight down the y axis, so we expect rho = 30 and theta = pi/2:
+    let mut lined = GrayscaleMap { pixels: vec![0; 100 * 100], size: (100, 100) };
+    for x in 0..100 {
+        lined.pixels[30 * 100 + x] = 255;
+    }
+    let lines = lined.hough_lines(128, 50);
+    assert_eq!(lines.len(), 1);
+    let (rho, theta) = lines[0];
+    assert!((rho - 30.0).abs() < 1e-9);
+    assert!((theta - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+
+    // A vertical line sits right at theta = 0, where its votes spill over into the cells just short of theta = pi with rho negated. Those are the same line, so it's still reported only once:
+    let mut lined = GrayscaleMap { pixels: vec![0; 100 * 100], size: (100, 100) };
+    for y in 0..100 {
+        lined.pixels[y * 100 + 40] = 255;
+    }
+    assert_eq!(lined.hough_lines(128, 50), vec![(40.0, 0.0)]);
+
+    // A blank image has no lines at all:
+    let blank = GrayscaleMap { pixels: vec![0; 100], size: (10, 10) };
+    assert!(blank.hough_lines(128, 0).is_empty());
+
+    // To find where a small pattern appears in a larger image, we slide the pattern over every position it fits and score how well it matches there. Normalized cross-correlation is the usual score: subtract each patch's mean, then take the correlation divided by the product of the two patches' spreads. That makes the score independent of brightness and contrast, and puts a perfect match at 1.0. A flat patch has no spread at all, so we score it 0.0 rather than dividing by zero:
+    // In src/grayscale.rs: GrayscaleMap::match_template
+
+    // We'll hide a patch cut from one image inside another, busy image, and see if we can find it again:
+    let noise = |seed: usize| move |i: usize| ((i * 7919 + seed) % 251) as u8;
+    let mut scene = GrayscaleMap { pixels: (0..20 * 15).map(noise(3)).collect(), size: (20, 15) };
+    let patch = GrayscaleMap { pixels: (0..4 * 3).map(noise(101)).map(|p| p / 2).collect(), size: (4, 3) };
+    for (y, row) in patch.pixels.chunks(4).enumerate() {
+        let start = (6 + y) * 20 + 11;
+        scene.pixels[start..start + 4].copy_from_slice(row);
+    }
+
+    let ((x, y), score) = scene.match_template(&patch).unwrap();
+    assert_eq!((x, y), (11, 6));
+    assert!((score - 1.0).abs() < 1e-9);
+
+    // A template bigger than the image can't match anywhere:
+    assert!(patch.match_template(&scene).is_none());
+
+    // So far we've been building maps with struct expressions, or with new_map, which panics if the pixel buffer doesn't match the size. That's fine for literals in our own code, but not for image data loaded from a file someone else gave us. A constructor that returns a Result lets the caller decide what to do with bad data. There's a second, sneakier way for the size to be wrong: on a 32-bit target, width * height can overflow usize, so we multiply with checked_mul rather than trusting the product:
+    // In src/grayscale.rs:
+    //     GrayscaleMapError
+    //     impl fmt::Display for GrayscaleMapError
+    //     impl Error for GrayscaleMapError
+    //     GrayscaleMap::new
+
+    let map = GrayscaleMap::new((3, 2), vec![0; 6]).unwrap();
+    assert_eq!(map.size, (3, 2));
+
+    assert_eq!(GrayscaleMap::new((3, 2), vec![0; 5]).err(),
+               Some(GrayscaleMapError::SizeMismatch { expected: 6, got: 5 }));
+    assert_eq!(GrayscaleMap::new((usize::MAX, 2), vec![]).err(),
+               Some(GrayscaleMapError::DimensionOverflow));
+
+    // The most basic thing to do with an image is read or write the pixel at (x, y). In our flat buffer, that's index y * width + x, but only if x and y are both in range; an x past the right edge would quietly wrap around to the next row. So get and set check both coordinates and report failure rather than touching the wrong pixel. For hot loops where the caller has already done the checking, the unsafe variants skip it:
+    // In src/grayscale.rs: GrayscaleMap::get, GrayscaleMap::set
+
+    let mut map = GrayscaleMap::new((3, 2), vec![0, 1, 2, 3, 4, 5]).unwrap();
+    assert_eq!(map.get(2, 1), Some(5));
+    assert_eq!(map.get(3, 0), None); // would wrap to (0, 1)
+    assert_eq!(map.get(0, 2), None);
+
+    assert!(map.set(1, 1, 40));
+    assert!(!map.set(3, 1, 40));
+    assert_eq!(map.pixels, vec![0, 1, 2, 3, 40, 5]);
+
+    unsafe {
+        map.set_unchecked(0, 1, 30);
+        assert_eq!(map.get_unchecked(0, 1), 30);
+    }
+
+    // Lots of image processing works one scanline at a time. Since the buffer is stored row by row, each row is just a width-sized chunk of it, and slices already know how to hand those out. The mutable version gives out non-overlapping &mut slices, so callers can transform rows in place:
+    // In src/grayscale.rs: GrayscaleMap::rows, GrayscaleMap::rows_mut
+
+    let mut map = GrayscaleMap::new((4, 3), (0..12).collect()).unwrap();
+    let sums: Vec<u32> = map.rows().map(|row| row.iter().map(|&p| p as u32).sum()).collect();
+    assert_eq!(sums, vec![6, 22, 38]);
+    assert_eq!(map.rows().count(), 3);
+    assert!(map.rows().all(|row| row.len() == 4));
+
+    for row in map.rows_mut() {
+        row.reverse();
+    }
+    assert_eq!(map.rows().next(), Some(&[3, 2, 1, 0][..]));
+
+    // Cropping copies a rectangular window out of the image into a map of its own. We copy one row of the window at a time, so the new buffer is in scanline order like any other. The window must lie entirely within the image; we use checked_add for the right and bottom edges so that a huge x or y can't wrap around and sneak past the check. An empty window is perfectly fine, and gives us a map with no pixels. (The center_crop method above is now just a crop with computed offsets.)
+    // In src/grayscale.rs: GrayscaleMap::crop
+
+    let map = GrayscaleMap::new((4, 3), (0..12).collect()).unwrap();
+    let window = map.crop(1, 1, 2, 2).unwrap();
+    assert_eq!(window.size, (2, 2));
+    assert_eq!(window.pixels, vec![5, 6, 9, 10]);
+
+    assert!(map.crop(3, 0, 2, 1).is_none());
+    assert!(map.crop(0, 2, 1, 2).is_none());
+    assert!(map.crop(usize::MAX, 0, 2, 1).is_none());
+
+    let empty = map.crop(2, 1, 0, 2).unwrap();
+    assert_eq!(empty.size, (0, 2));
+    assert!(empty.pixels.is_empty());
+
+    // Mirroring an image in place is mostly a matter of swapping. A horizontal flip reverses each row, which our rows_mut iterator makes easy. A vertical flip swaps whole rows, the top with the bottom, working inward. Borrowing two rows of the same buffer mutably at once would upset the borrow checker, so we use split_at_mut to cut the buffer into two halves that can each be borrowed separately:
+    // In src/grayscale.rs: GrayscaleMap::flip_horizontal, GrayscaleMap::flip_vertical
+
+    let original: Vec<u8> = (0..12).collect();
+    let mut map = GrayscaleMap::new((4, 3), original.clone()).unwrap();
+
+    map.flip_horizontal();
+    assert_eq!(map.pixels, vec![3, 2, 1, 0, 7, 6, 5, 4, 11, 10, 9, 8]);
+    map.flip_horizontal();
+    assert_eq!(map.pixels, original);
+
+    map.flip_vertical();
+    assert_eq!(map.pixels, vec![8, 9, 10, 11, 4, 5, 6, 7, 0, 1, 2, 3]);
+    map.flip_vertical();
+    assert_eq!(map.pixels, original);
+    assert_eq!(map.size, (4, 3));
+
+    // A quarter turn can't be done in place without a lot of fuss, since the width and height trade places, so these return new maps. Rotating clockwise, the pixel at (x, y) in a w x h image lands at (h - 1 - y, x) in the h x w result; counterclockwise, it lands at (y, w - 1 - x). It's easiest to build the result in scanline order and work out where each of its pixels came from:
+    // In src/grayscale.rs: GrayscaleMap::rotate_cw, GrayscaleMap::rotate_ccw
+
+    // A 2 x 3 map turns into a 3 x 2 map either way:
+    let map = GrayscaleMap::new((2, 3), vec![0, 1,
+                                             2, 3,
+                                             4, 5]).unwrap();
+    let cw = map.rotate_cw();
+    assert_eq!(cw.size, (3, 2));
+    assert_eq!(cw.pixels, vec![4, 2, 0,
+                               5, 3, 1]);
+    assert_eq!(cw.get(2, 0), map.get(0, 0)); // top-left goes to top-right
+    assert_eq!(cw.get(0, 1), map.get(1, 2)); // bottom-right goes to bottom-left
+
+    let ccw = map.rotate_ccw();
+    assert_eq!(ccw.size, (3, 2));
+    assert_eq!(ccw.pixels, vec![1, 3, 5,
+                                0, 2, 4]);
+    assert_eq!(ccw.rotate_cw().pixels, map.pixels);
+
+    // Two of the simplest point operations, which change each pixel without looking at any others, are inverting an image and thresholding it to pure black and white. These work in place, in a plain loop over the buffer, which the compiler is happy to vectorize:
+    // In src/grayscale.rs: GrayscaleMap::invert, GrayscaleMap::threshold
+
+    let mut map = GrayscaleMap::new((4, 1), vec![0, 99, 100, 255]).unwrap();
+    map.invert();
+    assert_eq!(map.pixels, vec![255, 156, 155, 0]);
+    map.invert();
+    map.threshold(100);
+    assert_eq!(map.pixels, vec![0, 0, 255, 255]);
+
+    // We've already been producing binary PGM data for base64 encoding, but it deserves proper support: writing to any io::Write, and reading from any io::Read. The header is the magic number P5, then the width, the height, and the maximum pixel value, all in ASCII and separated by whitespace, with # starting a comment that runs to the end of the line. A single whitespace byte separates the header from the raw pixels. Since a PGM file can come from anywhere, the reader checks everything, and reports problems as io::Errors of kind InvalidData, so they travel through ? alongside the I/O errors themselves. (The base64 methods above now use these.)
+    use std::io::{self, Read, Write};
+
+    // In src/grayscale.rs: GrayscaleMap::write_pgm, GrayscaleMap::read_pgm
+
+    // Writing to a Vec<u8> and reading back gives us the same map:
+    let map = GrayscaleMap::new((3, 2), vec![0, 10, 20, 200, 254, 255]).unwrap();
+    let mut file = Vec::new();
+    map.write_pgm(&mut file).unwrap();
+    assert_eq!(&file[..11], b"P5\n3 2\n255\n");
+
+    let copy = GrayscaleMap::read_pgm(&mut &file[..]).unwrap();
+    assert_eq!(copy.size, map.size);
+    assert_eq!(copy.pixels, map.pixels);
+
+    // Comments in the header are skipped:
+    let commented = b"P5\n# made by hand\n2 1 # tiny\n255\n\x07\x08";
+    assert_eq!(GrayscaleMap::read_pgm(&mut &commented[..]).unwrap().pixels, vec![7, 8]);
+
+    // But 16-bit images, other formats, garbled headers, and truncated data are all errors:
+    let read = |bytes: &[u8]| GrayscaleMap::read_pgm(&mut &bytes[..]).err().unwrap();
+    assert_eq!(read(b"P5\n1 1\n65535\n\x00\x00").kind(), io::ErrorKind::InvalidData);
+    assert_eq!(read(b"P2\n1 1\n255\n0").kind(), io::ErrorKind::InvalidData);
+    assert_eq!(read(b"P5\nwide 1\n255\n\x00").kind(), io::ErrorKind::InvalidData);
+    assert_eq!(read(b"P5\n2 2\n255\n\x00").to_string(), "PGM data is truncated: expected 4 pixels, got 1");
+    assert_eq!(read(b"P5\n1 1\n300\n\x00").to_string(), "unsupported PGM maximum value 300");
+    assert_eq!(read(b"P5\n1 1\n15\n\x10").to_string(), "PGM sample 16 exceeds maximum value 15");
+
+    // A header claiming an enormous image is caught when the data runs out, without first allocating room for it all:
+    assert_eq!(read(b"P5 100000 100000 255\n\x00").kind(), io::ErrorKind::InvalidData);
+
+    // Images with a maximum value below 255 are stretched to the full range:
+    let four_bit = b"P5\n4 1\n15\n\x00\x01\x08\x0f";
+    assert_eq!(GrayscaleMap::read_pgm(&mut &four_bit[..]).unwrap().pixels, vec![0, 17, 136, 255]);
+
+    // A box blur replaces each pixel with the average of the square of pixels around it. Done naively, that's (2r + 1)^2 additions per pixel. But a box blur is separable: blurring each row horizontally, and then each column of the result vertically, gives the same answer. And along a single line, we can keep a running sum of the window, adding the pixel that enters and subtracting the one that leaves as we slide along, so the cost per pixel doesn't depend on the radius at all. Near the edges, samples that would fall outside the image are clamped to the nearest pixel inside it:
+    // In src/grayscale.rs: GrayscaleMap::box_blur
+
+    // Blurring a flat image changes nothing, even at the edges:
+    let flat = GrayscaleMap::new((7, 5), vec![93; 35]).unwrap();
+    assert!(flat.box_blur(2).pixels.iter().all(|&p| p == 93));
+
+    // A single bright pixel spreads out over the box around it:
+    let mut dot = GrayscaleMap::new((5, 5), vec![0; 25]).unwrap();
+    dot.set(2, 2, 225);
+    let blurred = dot.box_blur(1);
+    assert_eq!(blurred.get(2, 2), Some(25));
+    assert_eq!(blurred.get(1, 3), Some(25));
+    assert_eq!(blurred.get(0, 0), Some(0));
+
+    // And a radius of zero is just a copy:
+    assert_eq!(dot.box_blur(0).pixels, dot.pixels);
+
+    // We've been comparing maps by their pixels and sizes field by field, and copying them by hand. Now that GrayscaleMap derives Clone, PartialEq, and Eq (see its definition at the top of the chapter), we don't have to. Debug is another matter: the derived impl would print every pixel, which for a real image means hundreds of thousands of numbers flooding the terminal. A hand-written impl can print the size and just count the pixels. Formatter's debug_struct helper takes care of the punctuation, and the {:#?} pretty-printing form, for us:
+    // In src/grayscale.rs: impl fmt::Debug for GrayscaleMap
+
+    let image = GrayscaleMap::new((1024, 576), vec![0; 1024 * 576]).unwrap();
+    assert_eq!(format!("{:?}", image), "GrayscaleMap { size: (1024, 576), pixels: 589824 bytes }");
+
+    let mut copy = image.clone();
+    assert_eq!(copy, image);
+    copy.set(0, 0, 1);
+    assert_ne!(copy, image);
+
+    // For test fixtures and procedural textures, it's convenient to describe an image as a function of its coordinates. from_fn calls the function once for every pixel, in scanline order, which is also the order the buffer is laid out in, so we can just push each result:
+    // In src/grayscale.rs: GrayscaleMap::from_fn
+
+    // An XOR texture:
+    let xor = GrayscaleMap::from_fn((256, 256), |x, y| ((x ^ y) & 0xff) as u8);
+    assert_eq!(xor.get(0b1010, 0b0110), Some(0b1100));
+
+    // The calls come in scanline order, once each:
+    let mut calls = Vec::new();
+    let map = GrayscaleMap::from_fn((3, 2), |x, y| {
+        calls.push((x, y));
+        (10 * y + x) as u8
+    });
+    assert_eq!(calls, vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+    assert_eq!(map, GrayscaleMap::new((3, 2), vec![0, 1, 2, 10, 11, 12]).unwrap());
+
+    // Crossfading between two frames means mixing them pixel by pixel: with alpha running from 0.0 to 1.0, each output pixel is self * (1 - alpha) + other * alpha, rounded to the nearest byte. Clamping alpha first keeps the results within 0..=255:
+    // In src/grayscale.rs: GrayscaleMap::blend
+
+    let black = GrayscaleMap::new((2, 1), vec![0, 100]).unwrap();
+    let white = GrayscaleMap::new((2, 1), vec![255, 200]).unwrap();
+    assert_eq!(black.blend(&white, 0.5).unwrap().pixels, vec![128, 150]);
+    assert_eq!(black.blend(&white, -1.0).unwrap(), black);
+    assert_eq!(black.blend(&white, 7.0).unwrap(), white);
+    assert!(black.blend(&GrayscaleMap::new((1, 2), vec![0, 0]).unwrap(), 0.5).is_none());
+
+    // For drawing overlays, we want to fill and outline rectangles. A rectangle that hangs off the edge of the image should simply be clipped, not cause a panic, so fill_rect trims the rectangle to the image first, using saturating_add so that enormous coordinates can't wrap around. An outline is then just four thin filled rectangles, each clipped the same way:
+    // In src/grayscale.rs: GrayscaleMap::fill_rect, GrayscaleMap::draw_rect
+
+    let mut canvas = GrayscaleMap::new((5, 4), vec![0; 20]).unwrap();
+    canvas.fill_rect(1, 1, 2, 2, 9);
+    assert_eq!(canvas.pixels, vec![0, 0, 0, 0, 0,
+                                   0, 9, 9, 0, 0,
+                                   0, 9, 9, 0, 0,
+                                   0, 0, 0, 0, 0]);
+
+    // Hanging off the right and bottom edges, and off the left and top edges (by starting at 0 with a large size, since coordinates are unsigned):
+    let mut canvas = GrayscaleMap::new((5, 4), vec![0; 20]).unwrap();
+    canvas.fill_rect(3, 2, 10, 10, 1);
+    assert_eq!(canvas.pixels, vec![0, 0, 0, 0, 0,
+                                   0, 0, 0, 0, 0,
+                                   0, 0, 0, 1, 1,
+                                   0, 0, 0, 1, 1]);
+    canvas.fill_rect(0, 0, 1, usize::MAX, 2);
+    canvas.fill_rect(0, 0, usize::MAX, 1, 3);
+    assert_eq!(canvas.pixels, vec![3, 3, 3, 3, 3,
+                                   2, 0, 0, 0, 0,
+                                   2, 0, 0, 1, 1,
+                                   2, 0, 0, 1, 1]);
+
+    // Entirely off-screen, nothing happens:
+    let before = canvas.clone();
+    canvas.fill_rect(5, 0, 3, 3, 7);
+    canvas.fill_rect(0, 4, 3, 3, 7);
+    canvas.fill_rect(usize::MAX, usize::MAX, 3, 3, 7);
+    assert_eq!(canvas, before);
+
+    // An outline clipped at the right and bottom loses those edges:
+    let mut canvas = GrayscaleMap::new((5, 4), vec![0; 20]).unwrap();
+    canvas.draw_rect(0, 0, 3, 3, 5);
+    canvas.draw_rect(3, 2, 4, 4, 8);
+    assert_eq!(canvas.pixels, vec![5, 5, 5, 0, 0,
+                                   5, 0, 5, 0, 0,
+                                   5, 5, 5, 8, 8,
+                                   0, 0, 0, 8, 0]);
+
+    // Blurring, sharpening, embossing, and edge detection are all the same operation with different weights: each output pixel is a weighted sum of the 3 x 3 neighborhood around it. convolve3x3 takes the nine weights in row-major order, divides the sum by divisor, adds offset, and clamps the result to a byte. As with sobel_energy, samples beyond the border are clamped to the nearest pixel inside the image:
+    // In src/grayscale.rs: GrayscaleMap::convolve3x3
+
+    // The identity kernel leaves the image unchanged:
+    let image = GrayscaleMap::from_fn((4, 3), |x, y| (x * 60 + y * 7) as u8);
+    assert_eq!(image.convolve3x3(&[0, 0, 0, 0, 1, 0, 0, 0, 0], 1, 0), image);
+
+    // A Laplacian kernel finds the edge of a bright square, and is zero in flat regions:
+    let mut square = GrayscaleMap::new((5, 5), vec![0; 25]).unwrap();
+    square.fill_rect(2, 0, 3, 5, 100);
+    let edges = square.convolve3x3(&[0, -1, 0, -1, 4, -1, 0, -1, 0], 1, 0);
+    assert_eq!(edges.rows().next().unwrap(), &[0, 0, 100, 0, 0][..]);
+
+    // And emboss uses the offset to center the result on mid-gray:
+    let flat = GrayscaleMap::new((3, 3), vec![50; 9]).unwrap();
+    let embossed = flat.convolve3x3(&[-2, -1, 0, -1, 1, 1, 0, 1, 2], 1, 128);
+    assert!(embossed.pixels.iter().all(|&p| p == 178));
+
+    // Effects like vignettes and gradients depend on where each pixel is. Rather than recovering x and y from a buffer index by hand every time, enumerate_pixels does it once: in scanline order, index i is at column i % width of row i / width. The mutable version hands out a &mut u8 for each pixel along with its coordinates:
+    // In src/grayscale.rs: GrayscaleMap::enumerate_pixels, GrayscaleMap::enumerate_pixels_mut
+
+    let mut gradient = GrayscaleMap::new((16, 9), vec![0; 16 * 9]).unwrap();
+    for (x, y, p) in gradient.enumerate_pixels_mut() {
+        *p = (x * 10 + y) as u8;
+    }
+    assert_eq!(gradient.get(15, 8), Some(158));
+    assert_eq!(gradient.enumerate_pixels().count(), 16 * 9);
+    assert_eq!(gradient.enumerate_pixels().last(), Some((15, 8, 158)));
+    assert!(gradient.enumerate_pixels().all(|(x, y, p)| gradient.get(x, y) == Some(p)));
+
+    // Tone adjustments map each gray level to a new one, and there are only 256 gray levels. So rather than calling powf for every pixel of a large image, each adjustment builds a 256-entry lookup table once and then runs the whole buffer through it. Contrast scales each value's distance from mid-gray, 128; gamma maps p to 255 * (p / 255)^g, so g below 1 brightens the shadows and g above 1 darkens them:
+    // In src/grayscale.rs: GrayscaleMap::apply_lut, GrayscaleMap::adjust_brightness, GrayscaleMap::adjust_contrast, GrayscaleMap::gamma
+
+    let ramp = GrayscaleMap::from_fn((256, 1), |x, _| x as u8);
+
+    let mut same = ramp.clone();
+    same.gamma(1.0);
+    assert_eq!(same, ramp);
+
+    let mut darker = ramp.clone();
+    darker.gamma(2.0);
+    assert_eq!((darker.get(0, 0), darker.get(128, 0), darker.get(255, 0)), (Some(0), Some(64), Some(255)));
+
+    let mut washed_out = ramp.clone();
+    washed_out.adjust_brightness(1000);
+    assert!(washed_out.pixels.iter().all(|&p| p == 255));
+    washed_out.adjust_brightness(i32::MIN);
+    assert!(washed_out.pixels.iter().all(|&p| p == 0));
+
+    let mut flat = ramp.clone();
+    flat.adjust_contrast(0.0);
+    assert!(flat.pixels.iter().all(|&p| p == 128));
+    let mut stark = ramp.clone();
+    stark.adjust_contrast(2.0);
+    assert_eq!((stark.get(32, 0), stark.get(128, 0), stark.get(200, 0)), (Some(0), Some(128), Some(255)));
+
+    // get and set are the careful way to reach a pixel. When the coordinates are known to be good, indexing syntax reads better: implementing Index and IndexMut for (usize, usize) lets us write map[(x, y)], and map[(x, y)] = 128. Like slice indexing, these panic when the coordinates are out of range. Checking x against the width matters: otherwise (width, 0) would quietly land on the first pixel of the next row:
+    // In src/grayscale.rs:
+    //     impl Index<(usize, usize)> for GrayscaleMap
+    //     impl IndexMut<(usize, usize)> for GrayscaleMap
+
+    let mut map = GrayscaleMap::new((3, 2), vec![0, 1, 2, 3, 4, 5]).unwrap();
+    assert_eq!(map[(2, 1)], 5);
+    map[(0, 1)] = 128;
+    assert_eq!(map.get(0, 1), Some(128));
+
+    let wide = std::panic::catch_unwind(|| map[(3, 0)]);
+    assert!(wide.is_err());
+
+    // With std::ops::Not, we can write the photographic negative of an image as !image. The impl for GrayscaleMap itself consumes its operand, so it can invert the buffer in place; the impl for &GrayscaleMap leaves the original alone and inverts a copy. Both are just invert under another name:
+    // In src/grayscale.rs:
+    //     impl Not for GrayscaleMap
+    //     impl Not for &GrayscaleMap
+
+    let original = GrayscaleMap::new((3, 1), vec![0, 100, 255]).unwrap();
+    let negative = !&original;
+    assert_eq!(negative.pixels, vec![255, 155, 0]);
+    assert_eq!(!negative, original);
+    assert_eq!(!!original.clone(), original);
+
+    // Transposing swaps the roles of x and y: the pixel at (x, y) moves to (y, x), and the width and height trade places. Unlike a quarter turn, it mirrors nothing, so doing it twice gets us back where we started. That makes it handy for separable filters, which can be written once for rows and applied to columns by transposing, filtering, and transposing back. from_fn builds the result in scanline order for us:
+    // In src/grayscale.rs: GrayscaleMap::transpose
+
+    let map = GrayscaleMap::new((2, 3), vec![0, 1,
+                                             2, 3,
+                                             4, 5]).unwrap();
+    let t = map.transpose();
+    assert_eq!(t.size, (3, 2));
+    assert_eq!(t.pixels, vec![0, 2, 4,
+                              1, 3, 5]);
+    assert!(t.enumerate_pixels().all(|(x, y, p)| map.get(y, x) == Some(p)));
+    assert_eq!(t.transpose(), map);
+
+    // GrayscaleMap::new, from_fn, resize_nearest, Bounds::area, and the Grid type below all multiply a width by a height, and a bare * on usize panics on overflow in debug builds and silently wraps in release builds, where a wrapped product would make from_fn allocate a tiny buffer and then try to fill it forever. So all of them now go through one helper that reports overflow as None. The constructors that return a Result turn that into DimensionOverflow; from_fn and resize_nearest, which can't report failure, panic with a clear message before doing any work:
+    // In src/bounds.rs: checked_area
+
+    assert_eq!(checked_area(1024, 576), Some(589824));
+    assert_eq!(checked_area(usize::MAX, usize::MAX), None);
+    assert_eq!(checked_area(usize::MAX, 0), Some(0));
+    assert_eq!(Bounds(usize::MAX, usize::MAX).area(), None);
+    assert_eq!(GrayscaleMap::new((usize::MAX, usize::MAX), vec![]).err(),
+               Some(GrayscaleMapError::DimensionOverflow));
+    assert!(std::panic::catch_unwind(|| GrayscaleMap::from_fn((usize::MAX, 2), |_, _| 0)).is_err());
+
+    // to_rgba has a three-byte sibling for contexts with no alpha channel. Going the other way, a color image has to be reduced to one gray level per pixel. Averaging the three channels would make pure green look as dark as pure blue, though the eye sees it as far brighter, so from_rgb_luminance weights them with the Rec. 601 luma coefficients, 0.299 R + 0.587 G + 0.114 B. Like new_map at the top of the chapter, it insists that the buffer's length match the size:
+    // In src/grayscale.rs: GrayscaleMap::to_rgb, GrayscaleMap::from_rgb_luminance
+
+    let map = GrayscaleMap::new((3, 1), vec![0, 128, 255]).unwrap();
+    assert_eq!(map.to_rgb(), vec![0, 0, 0, 128, 128, 128, 255, 255, 255]);
+    assert_eq!(GrayscaleMap::from_rgb_luminance(map.size, &map.to_rgb()), map);
+
+    let primaries = GrayscaleMap::from_rgb_luminance((3, 1), &[255, 0, 0,
+                                                               0, 255, 0,
+                                                               0, 0, 255]);
+    assert_eq!(primaries.pixels, vec![76, 150, 29]);
+
+    // A mipmap pyramid holds an image at successively halved resolutions, so a renderer can pick whichever level is closest to the size it's drawing at. Each level averages 2 x 2 blocks of the one before it. When a dimension is odd, the last row or column has no partner, and we simply drop it. We stop once either dimension is down to one pixel, since halving it again would leave nothing:
+    // In src/grayscale.rs: GrayscaleMap::pyramid
+
+    let levels = GrayscaleMap::from_fn((8, 8), |x, y| (x * 30 + y) as u8).pyramid();
+    let sizes: Vec<(usize, usize)> = levels.iter().map(|level| level.size).collect();
+    assert_eq!(sizes, vec![(8, 8), (4, 4), (2, 2), (1, 1)]);
+
+    // Odd dimensions round down, and the pyramid stops when the first dimension runs out:
+    let map = GrayscaleMap::new((5, 3), vec![10, 20, 30, 40, 99,
+                                             30, 40, 50, 60, 99,
+                                             99, 99, 99, 99, 99]).unwrap();
+    let levels = map.pyramid();
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels[1], GrayscaleMap::new((2, 1), vec![25, 45]).unwrap());
+
+    // Pasting one image onto another, at an offset, is how sprite sheets and collages get built. As with fill_rect, the parts of the source that land outside the destination are clipped, using saturating_add so that huge offsets can't wrap around, and a source that lands entirely off the edge changes nothing. Plain compositing copies the source over the destination; composite_max keeps the brighter of the two, which is how additive light sprites combine. Both are the same clipped loop with a different way of combining two pixels:
+    // In src/grayscale.rs: GrayscaleMap::composite, GrayscaleMap::composite_max, GrayscaleMap::composite_with
+
+    let sprite = GrayscaleMap::new((2, 2), vec![50, 60,
+                                                70, 80]).unwrap();
+
+    // Pasting at the bottom-right corner keeps only the sprite's top-left pixel:
+    let mut sheet = GrayscaleMap::new((3, 3), vec![0; 9]).unwrap();
+    sheet.composite(&sprite, 2, 2);
+    assert_eq!(sheet.pixels, vec![0, 0,  0,
+                                  0, 0,  0,
+                                  0, 0, 50]);
+
+    sheet.composite(&sprite, 0, 0);
+    assert_eq!(sheet.pixels, vec![50, 60,  0,
+                                  70, 80,  0,
+                                   0,  0, 50]);
+
+    // Entirely off the edge is a no-op:
+    let before = sheet.clone();
+    sheet.composite(&sprite, 3, 0);
+    sheet.composite(&sprite, usize::MAX, usize::MAX);
+    assert_eq!(sheet, before);
+
+    let mut lights = GrayscaleMap::new((2, 2), vec![65, 65, 65, 65]).unwrap();
+    lights.composite_max(&sprite, 0, 0);
+    assert_eq!(lights.pixels, vec![65, 65, 70, 80]);
+
+    // Screenshots and diagrams tend to have large flat areas, which run-length encoding stores compactly: each run of identical pixels, read in scanline order, becomes one (value, length) pair. A run can't be longer than a u32 can count, so a longer one is split into several pairs. Decoding checks the total length of the runs against the size before allocating anything, so a corrupt run list can neither produce a map that violates the size invariant nor ask for an absurd amount of memory:
+    // In src/grayscale.rs: GrayscaleMap::rle_encode, GrayscaleMap::rle_decode
+
+    let mut screenshot = GrayscaleMap::new((64, 48), vec![255; 64 * 48]).unwrap();
+    screenshot.fill_rect(0, 0, 64, 8, 40);
+    let runs = screenshot.rle_encode();
+    assert_eq!(runs, vec![(40, 64 * 8), (255, 64 * 40)]);
+    assert_eq!(GrayscaleMap::rle_decode(screenshot.size, &runs), Some(screenshot.clone()));
+
+    // Runs continue from the end of one row onto the next, so stripes alternating in scanline order are the worst case, one pair per pixel:
+    let stripes = GrayscaleMap::from_fn((5, 3), |x, y| if (x + y * 5) % 2 == 0 { 0 } else { 255 });
+    let runs = stripes.rle_encode();
+    assert_eq!(runs.len(), 15);
+    assert!(runs.iter().all(|&(_, len)| len == 1));
+    assert_eq!(GrayscaleMap::rle_decode((5, 3), &runs), Some(stripes));
+
+    assert_eq!(GrayscaleMap::rle_decode((2, 2), &[(7, 3)]), None);
+    assert_eq!(GrayscaleMap::rle_decode((2, 2), &[(7, 3), (8, u32::MAX)]), None);
+    assert_eq!(GrayscaleMap::rle_decode((0, 0), &[]), Some(GrayscaleMap::default()));
+
+    // invert, threshold, and the tone adjustments are all point operations, differing only in the function applied to each pixel. map_pixels takes that function as a closure, so callers can write their own without a new method for each; invert and threshold are now written in terms of map_pixels_mut. A closure that does real work, like a powf, runs once per pixel, so for an expensive function over a large image it's cheaper to build a 256-entry table once and use apply_lut, as gamma does:
+    // In src/grayscale.rs: GrayscaleMap::map_pixels, GrayscaleMap::map_pixels_mut
+
+    let map = GrayscaleMap::new((4, 1), vec![0, 100, 245, 250]).unwrap();
+    assert_eq!(map.map_pixels(|p| p.saturating_add(10)).pixels, vec![10, 110, 255, 255]);
+
+    let mut inverted = map.clone();
+    inverted.invert();
+    assert_eq!(inverted, map.map_pixels(|p| 255 - p));
+
+    let mut seen = Vec::new();
+    let mut copy = map.clone();
+    copy.map_pixels_mut(|p| { seen.push(p); p / 2 });
+    assert_eq!(seen, map.pixels);
+    assert_eq!(copy.pixels, vec![0, 50, 122, 125]);
+
+    // Custom stencil filters need each pixel's neighborhood. windows is the 2-D counterpart of slice's windows method: it yields every kw x kh block of pixels that lies entirely inside the image, moving in scanline order, as a Vec of the block's pixels in scanline order. Blocks that would hang off an edge are skipped, so there's no border handling for the caller to get wrong, at the cost of an output (kw - 1) columns narrower and (kh - 1) rows shorter. Like slice::windows, it panics on a zero-sized window:
+    // In src/grayscale.rs: GrayscaleMap::windows
+
+    let map = GrayscaleMap::from_fn((4, 4), |x, y| (y * 4 + x) as u8);
+    let windows: Vec<Vec<u8>> = map.windows(3, 3).collect();
+    assert_eq!(windows.len(), 4);
+    assert_eq!(windows[0], vec![0, 1, 2, 4, 5, 6, 8, 9, 10]);
+    assert_eq!(windows[3], vec![5, 6, 7, 9, 10, 11, 13, 14, 15]);
+
+    // A median filter, with no border logic of its own:
+    let medians: Vec<u8> = map.windows(3, 3).map(|mut w| { w.sort_unstable(); w[4] }).collect();
+    assert_eq!(medians, vec![5, 6, 9, 10]);
+
+    assert_eq!(map.windows(5, 1).count(), 0);
+    assert_eq!(map.windows(1, 1).count(), 16);
+
+    // Global histogram equalization, which CLAHE above refines, is worth having in its own right for low-contrast scans. First we need the histogram itself: how many pixels have each of the 256 gray levels. Equalizing remaps each level through the cumulative distribution function, the number of pixels at or below that level, stretched so that the darkest level present maps to 0 and the brightest to 255. The result spreads the levels out so each is about equally common. An image with only one gray level has nothing to stretch, and the formula would divide by zero, so we leave it alone:
+    // In src/grayscale.rs: GrayscaleMap::histogram, GrayscaleMap::equalize
+
+    let ramp = GrayscaleMap::from_fn((256, 2), |x, _| x as u8);
+    assert!(ramp.histogram().iter().all(|&count| count == 2));
+    let mut equalized = ramp.clone();
+    equalized.equalize();
+    assert_eq!(equalized, ramp);
+
+    // A dim, low-contrast scan gets stretched to the full range:
+    let mut scan = GrayscaleMap::new((4, 1), vec![100, 101, 102, 103]).unwrap();
+    scan.equalize();
+    assert_eq!(scan.pixels, vec![0, 85, 170, 255]);
+
+    // A flat image, and an empty one, are left as they are:
+    let mut flat = GrayscaleMap::new((3, 3), vec![77; 9]).unwrap();
+    flat.equalize();
+    assert!(flat.pixels.iter().all(|&p| p == 77));
+    let mut empty = GrayscaleMap::default();
+    empty.equalize();
+    assert_eq!(empty, GrayscaleMap::default());
+
+
+
+    // A Generic Grid<T>
+
+    // Nothing about a size and a row-major buffer is specific to gray bytes: an RGB image, a float heightmap, or a game board is laid out exactly the same way. Grid<T> is that layout with the element type left as a parameter. Rather than redefine GrayscaleMap in terms of it, and disturb every method above, we give GrayscaleMap a cheap bridge in each direction: both conversions just move the buffer, without copying it. Grid's constructor can fail in the same ways GrayscaleMap::new can, but a grid of booleans or RGB triples has elements, not pixels, so it has an error type of its own:
+    // In src/grid.rs:
+    //     Grid
+    //     GridError
+    //     impl fmt::Display for GridError
+    //     impl Error for GridError
+    //     Grid::new, Grid::from_fn, Grid::get, Grid::set, Grid::rows
+    //     GrayscaleMap::into_grid, GrayscaleMap::from_grid
+
+    let mut rgb = Grid::new((2, 2), vec![(0, 0, 0); 4]).unwrap();
+    assert!(rgb.set(1, 0, (255, 128, 0)));
+    assert!(!rgb.set(2, 0, (1, 1, 1)));
+    assert_eq!(rgb.get(1, 0), Some(&(255u8, 128u8, 0u8)));
+    assert_eq!(rgb.get(0, 2), None);
+    assert_eq!(rgb.rows().nth(1), Some(&[(0, 0, 0), (0, 0, 0)][..]));
+    let err = Grid::new((2, 2), vec![false; 3]).unwrap_err();
+    assert_eq!(err, GridError::SizeMismatch { expected: 4, got: 3 });
+    assert_eq!(err.to_string(), "expected 4 elements, got 3");
+    assert_eq!(Grid::new((usize::MAX, 2), Vec::<bool>::new()).err(), Some(GridError::DimensionOverflow));
+
+    let heights = Grid::from_fn((3, 2), |x, y| x as f32 + 0.5 * y as f32);
+    assert_eq!(heights.get(2, 1), Some(&2.5));
+
+    let map = GrayscaleMap::new((2, 1), vec![3, 4]).unwrap();
+    let grid = map.clone().into_grid();
+    assert_eq!(grid.get(1, 0), Some(&4));
+    assert_eq!(GrayscaleMap::from_grid(grid), map);
+
+
+
+    // Putting Broom to Work
+
+    // The broom example stops after a single cut, but in the story, the apprentice keeps chopping. We can keep splitting fragments until every broom is at most some height. A work list does the job without recursion: we take a broom off the list, and either it's short enough to keep, or we chop it and put both halves back on the list. Since chop appends " I" or " II" to each fragment's name, a broom's name spells out its path through the chopping, so the names all stay distinct. And since chop builds its fragments with .. b, every one of them keeps the original's health and intent.
+
+    // There's one trap: a min_height of 0 asks for brooms with no height at all. Depending on how chop rounds the halves, cutting a broom of height 1 may leave a fragment that's still 1 tall, so rather than risk looping forever, we treat a min_height of 0 as 1:
+    impl Broom {
+        /// Chop this broom, and then its fragments, until every broom's height
+        /// is at most `min_height`. A `min_height` of zero is treated as one.
+        /// A broom with no health left is returned whole, however tall.
+        fn chop_until(self, min_height: u32) -> Vec<Broom> {
+            let min_height = min_height.max(1);
+            let mut finished = Vec::new();
+            let mut pending = vec![self];
+
+            while let Some(broom) = pending.pop() {
+                if broom.height <= min_height || !broom.is_alive() {
+                    finished.push(broom);
+                } else {
+                    let (first, second) = chop(broom);
+                    pending.push(second);
+                    pending.push(first);
+                }
+            }
+
+            finished
+        }
+    }
+
+    let hokey = Broom {
+        name: "Hokey".to_string(),
+        height: 60,
+        health: 100,
+        position: (100.0, 200.0, 0.0),
+        intent: BroomIntent::DumpWater
+    };
+    let brooms = hokey.chop_until(16);
+    let names: Vec<&str> = brooms.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, vec!["Hokey I I", "Hokey I II", "Hokey II I", "Hokey II II"]);
+    for broom in &brooms {
+        assert_eq!(broom.height, 15);
+        assert_eq!(broom.health, 100);
+        assert!(matches!(broom.intent, BroomIntent::DumpWater));
+    }
+
+    // A min_height of zero gets us down to height 1, not an infinite loop:
+    let pokey = Broom { name: "Pokey".to_string(), height: 8, .. brooms.into_iter().next().unwrap() };
+    let splinters = pokey.chop_until(0);
+    assert_eq!(splinters.len(), 8);
+    assert!(splinters.iter().all(|b| b.height == 1));
+
+    // Writing out all five fields whenever we want a Broom gets tiresome, especially in tests where we only care about one or two of them. Functional update syntax helps when there's an existing broom to copy from, but not when there isn't. A builder fills that gap: it starts out with sensible defaults, each setter takes the builder by value and returns it so the calls can be chained, and build produces the Broom. The defaults are a healthy broom at the origin, fetching water, with an empty name and no height:
+    /// A chainable builder for `Broom` values, created by `Broom::builder`.
+    struct BroomBuilder {
+        broom: Broom
+    }
+
+    impl Broom {
+        /// Return a builder for a new `Broom`.
+        fn builder() -> BroomBuilder {
+            BroomBuilder {
+                broom: Broom {
+                    name: String::new(),
+                    height: 0,
+                    health: 100,
+                    position: (0.0, 0.0, 0.0),
+                    intent: BroomIntent::FetchWater
+                }
+            }
+        }
+    }
+
+    impl BroomBuilder {
+        fn name(mut self, name: &str) -> BroomBuilder {
+            self.broom.name = name.to_string();
+            self
+        }
+
+        fn height(mut self, height: u32) -> BroomBuilder {
+            self.broom.height = height;
+            self
+        }
+
+        fn health(mut self, health: u32) -> BroomBuilder {
+            self.broom.health = health;
+            self
+        }
+
+        fn position(mut self, position: (f32, f32, f32)) -> BroomBuilder {
+            self.broom.position = position;
+            self
+        }
+
+        fn intent(mut self, intent: BroomIntent) -> BroomBuilder {
+            self.broom.intent = intent;
+            self
+        }
+
+        fn build(self) -> Broom {
+            self.broom
+        }
+    }
+
+    let smokey = Broom::builder().name("Smokey").height(45).build();
+    assert_eq!(smokey.name, "Smokey");
+    assert_eq!(smokey.height, 45);
+    assert_eq!(smokey.health, 100);
+    assert_eq!(smokey.position, (0.0, 0.0, 0.0));
+    assert!(matches!(smokey.intent, BroomIntent::FetchWater));
+
+    let jokey = Broom::builder()
+        .name("Jokey")
+        .health(20)
+        .position((1.0, 2.0, 3.0))
+        .intent(BroomIntent::DumpWater)
+        .build();
+    assert_eq!((jokey.height, jokey.health), (0, 20));
+    assert!(matches!(jokey.intent, BroomIntent::DumpWater));
+
+    // For log output, we'd like brooms to print in plain English. Implementing Display lets us use a type with {} in format strings. BroomIntent's Display says what the broom is doing, and Broom's Display can use it in turn, simply by formatting self.intent with {}. For the position tuple, the {:?} format already prints what we want:
+    // In src/broom.rs: impl fmt::Display for BroomIntent
+
+    impl fmt::Display for Broom {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} ({}cm, {}hp) at {:?}, {}",
+                   self.name, self.height, self.health, self.position, self.intent)
+        }
+    }
+
+    let hokey = Broom::builder().name("Hokey").height(60).position((100.0, 200.0, 0.0)).build();
+    assert_eq!(hokey.to_string(), "Hokey (60cm, 100hp) at (100.0, 200.0, 0.0), fetching water");
+    assert_eq!(format!("{}", BroomIntent::DumpWater), "dumping water");
+
+    // An enchanted broom spends its life alternating between fetching water from the well and dumping it into the cauldron. Since BroomIntent is Copy, flipping it is just a match on the current value. A step of the simulation flips the intent and then moves the broom a little way toward wherever its new intent sends it, without overshooting:
+    const WELL: (f32, f32, f32) = (0.0, 0.0, 0.0);
+    const CAULDRON: (f32, f32, f32) = (30.0, 40.0, 0.0);
+    const BROOM_SPEED: f32 = 10.0;
+
+    impl Broom {
+        /// Switch between fetching and dumping water.
+        fn toggle_intent(&mut self) {
+            self.intent = match self.intent {
+                BroomIntent::FetchWater => BroomIntent::DumpWater,
+                BroomIntent::DumpWater => BroomIntent::FetchWater
+            };
+        }
+
+        /// Toggle the broom's intent, and then move it up to `BROOM_SPEED`
+        /// toward the well if it's fetching water, or toward the cauldron if
+        /// it's dumping water.
+        fn step(&mut self) {
+            self.toggle_intent();
+            let target = match self.intent {
+                BroomIntent::FetchWater => WELL,
+                BroomIntent::DumpWater => CAULDRON
+            };
+
+            self.move_toward(target, BROOM_SPEED);
+        }
+    }
+
+    let mut hokey = Broom::builder().name("Hokey").build();
+    hokey.toggle_intent();
+    assert!(matches!(hokey.intent, BroomIntent::DumpWater));
+    hokey.toggle_intent();
+    assert!(matches!(hokey.intent, BroomIntent::FetchWater));
+
+    // Stepping from the well sends the broom toward the cauldron, 50 units away, and the next step turns it around:
+    hokey.step();
+    assert!(matches!(hokey.intent, BroomIntent::DumpWater));
+    assert_eq!(hokey.position, (6.0, 8.0, 0.0));
+    hokey.step();
+    assert_eq!(hokey.position, WELL);
+
+    // Chopping with b.height / 2 for both halves used to lose a unit of height whenever the original was odd: a broom 61 tall became two brooms 30 tall. chop now gives the first fragment the larger half, so the two always add back up to the original:
+    for &height in &[60, 61] {
+        let broom = Broom::builder().name("Hokey").height(height).build();
+        let (broom1, broom2) = chop(broom);
+        assert_eq!(broom1.height + broom2.height, height);
+        assert!(broom1.height - broom2.height <= 1);
+    }
+
+    // So far health has just been along for the ride. A broom takes damage and can be healed, but its health never drops below zero, which saturating_sub guarantees without any risk of u32 underflow, and never rises above MAX_HEALTH. A broom with no health left is dead. Chopping a dead broom would only produce two more dead brooms, so chop_until now leaves one whole instead of splitting it, whatever its height. chop itself, being the primitive that always returns two halves, still splits whatever it's given; callers that care should check is_alive first, as chop_until does:
+    const MAX_HEALTH: u32 = 100;
+
+    impl Broom {
+        /// Reduce the broom's health by `amount`, stopping at zero.
+        fn take_damage(&mut self, amount: u32) {
+            self.health = self.health.saturating_sub(amount);
+        }
+
+        /// Increase the broom's health by `amount`, up to `MAX_HEALTH`.
+        fn heal(&mut self, amount: u32) {
+            self.health = self.health.saturating_add(amount).min(MAX_HEALTH);
+        }
+
+        /// Return true if the broom has any health left.
+        fn is_alive(&self) -> bool {
+            self.health > 0
+        }
+    }
+
+    let mut broom = Broom::builder().name("Hokey").height(60).health(30).build();
+    broom.take_damage(10);
+    assert_eq!(broom.health, 20);
+    broom.heal(500);
+    assert_eq!(broom.health, MAX_HEALTH);
+    broom.take_damage(u32::MAX);
+    assert_eq!(broom.health, 0);
+    assert!(!broom.is_alive());
+
+    // A dead broom stays in one piece, while a living one is chopped as usual:
+    let remains = broom.chop_until(16);
+    assert_eq!(remains.len(), 1);
+    assert_eq!(remains[0].height, 60);
+    assert_eq!(Broom::builder().name("Pokey").height(60).build().chop_until(16).len(), 4);
+
+    // step has been doing its own vector arithmetic on the position tuple. That's worth pulling out into methods of its own, so the rest of a simulation can move brooms around too; step now just calls move_toward. move_toward moves straight at the target, but if the target is no more than step away, it lands exactly on it rather than computing a position that floating-point rounding might leave a hair short or past:
+    impl Broom {
+        /// Move the broom by the given offsets.
+        fn move_by(&mut self, dx: f32, dy: f32, dz: f32) {
+            let (x, y, z) = self.position;
+            self.position = (x + dx, y + dy, z + dz);
+        }
+
+        /// Return the straight-line distance between `self` and `other`.
+        fn distance_to(&self, other: &Broom) -> f32 {
+            let (x, y, z) = self.position;
+            let (ox, oy, oz) = other.position;
+            let (dx, dy, dz) = (ox - x, oy - y, oz - z);
+            (dx * dx + dy * dy + dz * dz).sqrt()
+        }
+
+        /// Move the broom up to `step` units straight toward `target`,
+        /// stopping at the target rather than overshooting it.
+        fn move_toward(&mut self, target: (f32, f32, f32), step: f32) {
+            let (x, y, z) = self.position;
+            let (dx, dy, dz) = (target.0 - x, target.1 - y, target.2 - z);
+            let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+            if distance <= step {
+                self.position = target;
+            } else {
+                let scale = step / distance;
+                self.move_by(dx * scale, dy * scale, dz * scale);
+            }
+        }
+    }
+
+    let mut hokey = Broom::builder().name("Hokey").build();
+    hokey.move_by(3.0, 4.0, 12.0);
+    let pokey = Broom::builder().name("Pokey").build();
+    assert_eq!(hokey.distance_to(&pokey), 13.0);
+    assert_eq!(pokey.distance_to(&hokey), 13.0);
+
+    hokey.move_toward(WELL, 6.5);
+    assert_eq!(hokey.position, (1.5, 2.0, 6.0));
+    hokey.move_toward((0.1, 0.2, 0.3), f32::MAX);
+    assert_eq!(hokey.position, (0.1, 0.2, 0.3));
+
+    // To draw brooms back to front, we need them in order of height. We could implement Ord for Broom by comparing heights, but then two brooms of the same height but different names would compare as equal, which is a strange thing for Ord to claim. A free function keeps the ordering explicit at the call site. sort_by_key is a stable sort, so brooms of equal height keep their relative order:
+    fn sort_by_height(brooms: &mut [Broom]) {
+        brooms.sort_by_key(|b| b.height);
+    }
+
+    let mut brooms: Vec<Broom> = [("Hokey", 60), ("Pokey", 30), ("Smokey", 90)].iter()
+        .map(|&(name, height)| Broom::builder().name(name).height(height).health(height / 3).build())
+        .collect();
+    sort_by_height(&mut brooms);
+    let sorted: Vec<(&str, u32, u32)> = brooms.iter()
+        .map(|b| (b.name.as_str(), b.height, b.health))
+        .collect();
+    assert_eq!(sorted, vec![("Pokey", 30, 10), ("Hokey", 60, 20), ("Smokey", 90, 30)]);
+
+    // A whole swarm of brooms takes turns: the broom at the front of the line does one unit of work, switching between fetching and dumping, and goes to the back of the line. That's just what the generic Queue<T> from later in this chapter is for, and since Queue owns its elements, it holds Brooms, with their Strings, as happily as it holds chars. A broom's health can change while it waits, so rather than keep a count that could go stale, the swarm counts its living brooms by walking the queue whenever it's asked. A dead broom still takes its turn in line, but does no work:
+    struct BroomSwarm {
+        waiting: Queue<Broom>
+    }
+
+    impl BroomSwarm {
+        fn new() -> BroomSwarm {
+            BroomSwarm { waiting: Queue::new() }
+        }
+
+        /// Add `b` to the back of the line.
+        fn enqueue(&mut self, b: Broom) {
+            self.waiting.push(b);
+        }
+
+        /// Let the broom at the front of the line toggle its intent, if it's
+        /// alive, and send it to the back of the line.
+        fn tick(&mut self) {
+            if let Some(mut b) = self.waiting.pop() {
+                if b.is_alive() {
+                    b.toggle_intent();
+                }
+                self.waiting.push(b);
+            }
+        }
+
+        /// Return the number of brooms in the swarm, living or dead.
+        fn len(&self) -> usize {
+            self.waiting.len()
+        }
+
+        /// Return the number of living brooms in the swarm.
+        fn active(&self) -> usize {
+            self.waiting.iter().filter(|b| b.is_alive()).count()
+        }
+    }
+
+    let mut swarm = BroomSwarm::new();
+    for name in &["Hokey", "Pokey", "Smokey"] {
+        swarm.enqueue(Broom::builder().name(name).height(60).build());
+    }
+    swarm.enqueue(Broom::builder().name("Broken").health(0).build());
+    assert_eq!((swarm.len(), swarm.active()), (4, 3));
+
+    // Seven ticks: the first three brooms get two turns each, and the dead one gets one:
+    for _ in 0..7 {
+        swarm.tick();
+    }
+    assert_eq!(swarm.len(), 4);
+    let mut names: Vec<&str> = swarm.waiting.iter().map(|b| b.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Broken", "Hokey", "Pokey", "Smokey"]);
+    assert!(swarm.waiting.iter().all(|b| b.intent == BroomIntent::FetchWater));
+
+    // The dead broom is at the front of the line now. Once it has taken its turn, if the broom behind it wears out, the count follows:
+    swarm.tick();
+    swarm.waiting.peek_mut().unwrap().health = 0;
+    assert_eq!((swarm.len(), swarm.active()), (4, 2));
+
+    // Scenario files describe brooms in text, so we want to parse intents from strings. (BroomIntent now derives Debug too, so that results holding one can be unwrapped and compared with assert_eq!.) The standard trait for that is FromStr, which is what str's parse method calls; TryFrom<&str> just forwards to it, for code that prefers that spelling. Parsing ignores case, and accepts the short names "fetch" and "dump", the snake_case variant names, and the phrases BroomIntent's Display produces, so formatting an intent and parsing it back gets the same intent:
+    // In src/broom.rs:
+    //     InvalidIntent
+    //     impl fmt::Display for InvalidIntent
+    //     impl Error for InvalidIntent
+    //     impl FromStr for BroomIntent
+    //     impl TryFrom<&str> for BroomIntent
+
+    assert_eq!("FETCH".parse(), Ok(BroomIntent::FetchWater));
+    assert_eq!(BroomIntent::try_from("Dump_Water"), Ok(BroomIntent::DumpWater));
+    for &intent in &[BroomIntent::FetchWater, BroomIntent::DumpWater] {
+        assert_eq!(intent.to_string().parse(), Ok(intent));
+    }
+
+    let err = "sweep".parse::<BroomIntent>().unwrap_err();
+    assert_eq!(err, InvalidIntent("sweep".to_string()));
+    assert_eq!(err.to_string(), "unknown broom intent \"sweep\"");
     
 
 
     // Tuple-Like Structs
 
     // The second kind of struct type is called a tuple-like struct, because it resembles a tuple:
-    struct Bounds(usize, usize);
+    // struct Bounds(usize, usize);
 
     // We construct a value of this type much as we would construct a tuple, except that we must include the struct name:
     let image_bounds = Bounds(1024, 768);
@@ -121,20 +1270,300 @@ fn main() {
     assert_eq!(image_bounds.0 * image_bounds.1, 786432);
 
     // Individual elements of a tuple-like struct may be public or not:
-    pub struct Bounds(pub usize, pub usize);
+    // #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+    // #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    // pub struct Bounds(pub usize, pub usize);
 
     // The expression Bounds(1024, 768) looks like a function call, and in fact it is. Defining the type also implicitly defines a function:
-    fn Bounds(elem0: usize, elem1: usize) -> Bounds { ... }
+    // fn Bounds(elem0: usize, elem1: usize) -> Bounds { ... }
 
     // At the most fundamental level, named-field and tuple-like structs are very similar. The choice of which to use comes down to questions of legibility, ambiguity, and brevity. If we will use the . operator to get at a value's components much at all, identifying fields by name provides the reader more info, and is probably more robust against types. If we will usually use pattern matching to find the elements, tuple-like structs can work nicely.
 
     // Tuple-like structs are good for newtypes, structs with a single component that we define to get stricter type checking. For example, if we are working with ASCII-only text, we might define a newtype like so:
+    #[derive(Clone, Debug, PartialEq, Eq)]
     struct Ascii(Vec<u8>);
 
     // Using this type for our ASCII strings is much better than simply passing around Vec<u8> buffers and explaining what they are in the comments. The newtype helps Rust catch mistakes where some other byte buffer is passed to a function expecting ASCII text. More on this in chapt 21.
 
 
 
+    // Giving Bounds Some Behaviour
+
+    // Bounds holds a width and a height, but it can't do anything with them yet. Like named-field structs, tuple-like structs can have impl blocks, and their methods get at the elements as self.0 and self.1.
+
+    // The area is the product of the two, which can overflow usize for large enough values. We return an Option, with None meaning the area is too large to represent, rather than letting the multiplication panic in debug builds or wrap around in release builds. The aspect ratio is a floating-point division, and dividing by a height of zero would give infinity for a positive width but NaN for a zero width. A NaN compares unequal to everything, including itself, which tends to cause trouble downstream, so we make any zero-height bounds infinitely wide:
+    // In src/bounds.rs: Bounds::area, Bounds::aspect_ratio
+
+    assert_eq!(Bounds(1024, 768).area(), Some(786432));
+    assert_eq!(Bounds(usize::MAX, 2).area(), None);
+    assert_eq!(Bounds(1920, 1080).aspect_ratio(), 16.0 / 9.0);
+    assert_eq!(Bounds(5, 0).aspect_ratio(), f64::INFINITY);
+    assert_eq!(Bounds(0, 0).aspect_ratio(), f64::INFINITY);
+
+    // For layout math, it's handy to scale and combine bounds with ordinary arithmetic operators. Rust's operators are defined by traits in std::ops: implementing Mul<usize> for Bounds makes bounds * n work, and Add for Bounds makes a + b work. (Bounds now derives Copy, Clone, Debug, and PartialEq, so that we can use it by value freely and compare it in assertions.) Layout code would rather get a too-large size than a panic or a tiny wrapped-around one, so the arithmetic saturates at usize::MAX. Scaling by a fraction needs floating point; converting back with as rounds toward zero and saturates, so we round first, and a negative or NaN factor gives zero:
+    use std::ops::{Add, Mul};
+
+    // In src/bounds.rs:
+    //     impl Mul<usize> for Bounds
+    //     impl Add for Bounds
+    //     Bounds::scale
+
+    assert_eq!(Bounds(4, 3) * 2, Bounds(8, 6));
+    assert_eq!(Bounds(4, 3) + Bounds(1, 10), Bounds(5, 13));
+    assert_eq!(Bounds(usize::MAX, 3) * 2, Bounds(usize::MAX, 6));
+    assert_eq!(Bounds(usize::MAX, 3) + Bounds(1, 1), Bounds(usize::MAX, 4));
+    assert_eq!(Bounds(1920, 1080).scale(0.25), Bounds(480, 270));
+    assert_eq!(Bounds(3, 5).scale(0.5), Bounds(2, 3));
+    assert_eq!(Bounds(3, 5).scale(-1.0), Bounds(0, 0));
+
+    // To move between Bounds and the (width, height) tuples used elsewhere, like GrayscaleMap's size field, we implement From in both directions. Implementing From also gives us the matching Into for free:
+    // In src/bounds.rs:
+    //     impl From<(usize, usize)> for Bounds
+    //     impl From<Bounds> for (usize, usize)
+
+    let map = GrayscaleMap::new((4, 3), vec![0; 12]).unwrap();
+    let bounds = Bounds::from(map.size);
+    assert_eq!(bounds.area(), Some(map.pixels.len()));
+    let size: (usize, usize) = (bounds * 2).into();
+    assert_eq!(size, (8, 6));
+
+    // If we think of Bounds(w, h) as a rectangle with its top-left corner at the origin, a point (x, y) is inside it when 0 <= x < w and 0 <= y < h. The upper edges are exclusive, just like a slice's indices, so a point at x == w is outside. That's exactly the check GrayscaleMap's get and set need, so they now use it too:
+    // In src/bounds.rs: Bounds::contains
+
+    let bounds = Bounds(4, 3);
+    assert!(bounds.contains(0, 0));
+    assert!(bounds.contains(3, 2));
+    assert!(!bounds.contains(4, 0));
+    assert!(!bounds.contains(0, 3));
+    assert!(!Bounds(0, 0).contains(0, 0));
+
+    // For packing rectangles, it's handy to sort them by size. Bounds orders by area, then by width, then by height. That's an ordering by size, not by containment: Bounds(1, 10) sorts before Bounds(3, 4), though neither fits inside the other. The width and height tiebreakers make it a total order consistent with the derived Eq, so only equal bounds compare as Equal; height only matters when the width is zero. Computing the area in u128 means the comparison can't overflow, even for bounds whose area() is None:
+    // In src/bounds.rs:
+    //     impl Ord for Bounds
+    //     impl PartialOrd for Bounds
+
+    let mut rects = vec![Bounds(2, 3), Bounds(1, 1), Bounds(3, 2)];
+    rects.sort();
+    assert_eq!(rects, vec![Bounds(1, 1), Bounds(2, 3), Bounds(3, 2)]);
+    assert!(Bounds(1, 10) < Bounds(3, 4));
+    assert!(Bounds(0, 3) < Bounds(0, 5));
+    assert!(Bounds(usize::MAX, 2) > Bounds(usize::MAX, 1));
+
+
+
+    // Enforcing the Ascii Invariant
+
+    // The Ascii newtype only catches mistakes if nothing can get into it that isn't actually ASCII. Since its element isn't pub, code outside this module can't write Ascii(bytes) directly, so if the only public way to make one checks the bytes, every Ascii value is guaranteed to hold valid ASCII. The standard trait for a conversion that can fail is TryFrom. (It's not in the 2018 edition's prelude, so we import it.) When the check fails, the error says where the first bad byte is and what it was:
+    use std::convert::TryFrom;
+
+    /// The error returned when a byte buffer isn't valid ASCII.
+    #[derive(Debug, PartialEq)]
+    pub struct NotAsciiError {
+        pub index: usize,
+        pub byte: u8
+    }
+
+    impl fmt::Display for NotAsciiError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "byte {:#04x} at index {} is not ASCII", self.byte, self.index)
+        }
+    }
+
+    impl Error for NotAsciiError {}
+
+    impl TryFrom<Vec<u8>> for Ascii {
+        type Error = NotAsciiError;
+
+        fn try_from(bytes: Vec<u8>) -> Result<Ascii, NotAsciiError> {
+            if let Some(index) = bytes.iter().position(|&b| b >= 0x80) {
+                return Err(NotAsciiError { index, byte: bytes[index] });
+            }
+            Ok(Ascii(bytes))
+        }
+    }
+
+    // Every ASCII string is also valid UTF-8, so converting an Ascii into a String can't fail. Normally String::from_utf8 would check the bytes all over again, returning a Result. The unsafe String::from_utf8_unchecked skips the check, and our invariant is what makes it safe to call here:
+    impl Ascii {
+        /// Return the text as a byte slice.
+        pub fn as_bytes(&self) -> &[u8] {
+            &self.0
+        }
+
+        /// Convert the text into a `String`, without copying or re-checking it.
+        pub fn into_string(self) -> String {
+            // SAFETY: an `Ascii` only ever holds bytes below 0x80, and every
+            // such sequence is valid UTF-8.
+            unsafe { String::from_utf8_unchecked(self.0) }
+        }
+    }
+
+    let ascii = Ascii::try_from(b"ASCII and ye shall receive".to_vec()).unwrap();
+    assert_eq!(ascii.as_bytes()[0], b'A');
+    assert_eq!(ascii.into_string(), "ASCII and ye shall receive");
+
+    let err = Ascii::try_from("caf\u{e9}".as_bytes().to_vec()).unwrap_err();
+    assert_eq!(err, NotAsciiError { index: 3, byte: 0xc3 });
+    assert_eq!(err.to_string(), "byte 0xc3 at index 3 is not ASCII");
+
+    // By the same reasoning, an &Ascii can be viewed as an &str without any checking. Implementing Deref with Target = str makes that view automatic: method calls on an Ascii that Ascii doesn't define itself are looked up on str, so all of str's methods, and indexing by ranges, work on our type for free:
+    use std::ops::Deref;
+
+    impl Deref for Ascii {
+        type Target = str;
+
+        fn deref(&self) -> &str {
+            // SAFETY: an `Ascii` only ever holds bytes below 0x80, and every
+            // such sequence is valid UTF-8.
+            unsafe { std::str::from_utf8_unchecked(&self.0) }
+        }
+    }
+
+    let ascii = Ascii::try_from(b"rusty old brooms".to_vec()).unwrap();
+    assert_eq!(ascii.to_uppercase(), "RUSTY OLD BROOMS");
+    assert_eq!(ascii.split_whitespace().collect::<Vec<&str>>(), vec!["rusty", "old", "brooms"]);
+    assert_eq!(&ascii[6..9], "old");
+    assert!(ascii.ends_with("brooms"));
+
+    // Some operations can't break the invariant no matter what, so they don't need to check anything. Changing the case of ASCII letters leaves every byte below 0x80, and so does gluing two ASCII strings together. Since these methods are defined on Ascii itself, they take precedence over str's methods of the same name, which return a String rather than an Ascii. Implementing Add lets us write a + b for concatenation, the way String does:
+    impl Ascii {
+        /// Return a copy with every lowercase letter changed to uppercase.
+        pub fn to_ascii_uppercase(&self) -> Ascii {
+            Ascii(self.0.to_ascii_uppercase())
+        }
+
+        /// Return a copy with every uppercase letter changed to lowercase.
+        pub fn to_ascii_lowercase(&self) -> Ascii {
+            Ascii(self.0.to_ascii_lowercase())
+        }
+
+        /// Append `other` to the end of this text.
+        pub fn push_str(&mut self, other: &Ascii) {
+            self.0.extend_from_slice(&other.0);
+        }
+    }
+
+    impl Add for Ascii {
+        type Output = Ascii;
+
+        fn add(mut self, other: Ascii) -> Ascii {
+            self.push_str(&other);
+            self
+        }
+    }
+
+    let ascii = Ascii::try_from(b"abc123".to_vec()).unwrap();
+    assert_eq!(&*ascii.to_ascii_uppercase(), "ABC123");
+    assert_eq!(&*ascii.to_ascii_uppercase().to_ascii_lowercase(), "abc123");
+
+    let mut greeting = Ascii::try_from(b"Hello, ".to_vec()).unwrap();
+    greeting.push_str(&Ascii::try_from(b"world".to_vec()).unwrap());
+    let greeting = greeting + Ascii::try_from(b"!".to_vec()).unwrap();
+    assert_eq!(greeting.into_string(), "Hello, world!");
+
+
+
+    // A Saturating Luminance Newtype
+
+    // Newtypes are good for more than enforcing invariants. Pixel arithmetic on bare u8 values wraps around or panics on overflow, depending on the build; a Luminance newtype can make every operation saturate instead, so brightening an almost-white pixel gives white rather than black. Since the only field is a u8, the type is Copy and costs nothing over the byte itself:
+    // In src/luminance.rs:
+    //     Luminance
+    //     impl Add<u8> for Luminance
+    //     impl Sub<u8> for Luminance
+    //     impl Mul<f64> for Luminance
+    //     GrayscaleMap::get_luminance
+
+    assert_eq!(Luminance(200) + 100u8, Luminance(255));
+    assert_eq!(Luminance(0) + 255u8, Luminance(255));
+    assert_eq!(Luminance(100) - 150u8, Luminance(0));
+    assert_eq!(Luminance(255) - 5u8, Luminance(250));
+    assert_eq!(Luminance(200) * 2.0, Luminance(255));
+    assert_eq!(Luminance(200) * -1.0, Luminance(0));
+    assert_eq!(Luminance(100) * 0.5, Luminance(50));
+
+    let map = GrayscaleMap::new((2, 1), vec![10, 250]).unwrap();
+    assert_eq!(map.get_luminance(1, 0).map(|l| l + 10u8), Some(Luminance(255)));
+    assert_eq!(map.get_luminance(2, 0), None);
+
+
+
+    // One Error Type for the Chapter
+
+    // We now have three kinds of error from three kinds of fallible operation: GrayscaleMapError from GrayscaleMap::new, NotAsciiError from Ascii::try_from, and io::Error from read_pgm. A function that does all three can't use ? with its Result unless they all convert into the function's error type. CrateError is an enum with one variant wrapping each, and a From impl for each, which is exactly what ? looks for. Its Display adds a word of context to the wrapped error's own message, and source hands back the wrapped error, for callers that want to dig deeper:
+    /// Any error produced by the types in this chapter.
+    #[derive(Debug)]
+    pub enum CrateError {
+        Image(GrayscaleMapError),
+        Ascii(NotAsciiError),
+        Io(io::Error)
+    }
+
+    impl fmt::Display for CrateError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                CrateError::Image(e) => write!(f, "invalid image: {}", e),
+                CrateError::Ascii(e) => write!(f, "invalid text: {}", e),
+                CrateError::Io(e) => write!(f, "I/O error: {}", e)
+            }
+        }
+    }
+
+    impl Error for CrateError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match self {
+                CrateError::Image(e) => Some(e),
+                CrateError::Ascii(e) => Some(e),
+                CrateError::Io(e) => Some(e)
+            }
+        }
+    }
+
+    impl From<GrayscaleMapError> for CrateError {
+        fn from(e: GrayscaleMapError) -> CrateError {
+            CrateError::Image(e)
+        }
+    }
+
+    impl From<NotAsciiError> for CrateError {
+        fn from(e: NotAsciiError) -> CrateError {
+            CrateError::Ascii(e)
+        }
+    }
+
+    impl From<io::Error> for CrateError {
+        fn from(e: io::Error) -> CrateError {
+            CrateError::Io(e)
+        }
+    }
+
+    // Now one function can load an image, check that its caption is ASCII, and crop the image to a requested size, using ? for all three:
+    fn load_captioned(pgm: &[u8], caption: Vec<u8>, size: (usize, usize))
+        -> Result<(GrayscaleMap, Ascii), CrateError>
+    {
+        let image = GrayscaleMap::read_pgm(&mut &pgm[..])?;
+        let caption = Ascii::try_from(caption)?;
+        let area = checked_area(size.0, size.1).ok_or(GrayscaleMapError::DimensionOverflow)?;
+        let pixels = image.pixels.into_iter().take(area).collect();
+        Ok((GrayscaleMap::new(size, pixels)?, caption))
+    }
+
+    let pgm = b"P5\n2 2\n255\n\x01\x02\x03\x04";
+    let (image, caption) = load_captioned(pgm, b"tiles".to_vec(), (2, 1)).unwrap();
+    assert_eq!(image.pixels, vec![1, 2]);
+    assert_eq!(&*caption, "tiles");
+
+    let err = load_captioned(b"P2\n", b"tiles".to_vec(), (2, 1)).unwrap_err();
+    assert!(matches!(err, CrateError::Io(_)));
+    assert!(err.to_string().starts_with("I/O error: "));
+
+    let err = load_captioned(pgm, b"caf\xc3\xa9".to_vec(), (2, 1)).unwrap_err();
+    assert_eq!(err.to_string(), "invalid text: byte 0xc3 at index 3 is not ASCII");
+    assert!(err.source().is_some());
+
+    let err = load_captioned(pgm, b"tiles".to_vec(), (3, 3)).unwrap_err();
+    assert_eq!(err.to_string(), "invalid image: expected 9 pixels, got 4");
+
+
+
     // Unit-Like Structs
 
     // The third kind of struct is a little obscure. It declares a struct type with no elements at all:
@@ -152,10 +1581,10 @@ fn main() {
     // Struct Layout
 
     // In memory, both named-field and tuple-like structs are the same thing. A collection of values, of possibly mixed types, laid out in a particular way in memory. For example, earlier in the chapter we defined:
-    struct GrayscaleMap {
-        pixels: Vec<u8>,
-        size: (usize, usize)
-    }
+    // struct GrayscaleMap {
+    //     pixels: Vec<u8>,
+    //     size: (usize, usize)
+    // }
 
     // See page 315 for diagram
 
@@ -170,38 +1599,38 @@ fn main() {
     // Throughout the book we've been calling methods on all sorts of values. We've pushed elements onto vectors with v.push(e), fetched their length with v.len(), checked Result values for errors with r.expect("msg"), and so on.
 
     // We can define methods on any struct type we define. Rather than appearing inside the struct definition (C++ or Java), Rust methods appear in a separate impl block:
-    /// A last-in, first-out queue of characters.
-    pub struct Queue {
-        older: Vec<char>, // older elements, eldest last.
-        younger: Vec<char> // younger elements, youngest last.
-    }
-
-    impl Queue {
-        /// Push a character onto the back of a queue.
-        pub fn push(&mut self, c: char) {
-            self.younger.push(c);
-        }
-
-        /// Pop a character off the front of a queue. Return `Some(c)` if there
-        /// was a character to pop, or `None` if the queue was empty.
-        pub fn pop(&mut self) -> Option<char> {
-            if self.older.is_empty() {
-                if self.younger.is_empty() {
-                    return None;
-                }
-
-                // Bring the elements in younger over to older, and put them in
-                // the promised order.
-                use std::mem::swap;
-                swap(&mut self.older, &mut self.younger);
-                self.older.reverse();
-            }
-
-            // Now older is guaranteed to have something. Vec's pop method
-            // already returns an Option, so we're set.
-            self.older.pop()
-        }
-    }
+    // /// A last-in, first-out queue of characters.
+    // pub struct Queue {
+    //     older: Vec<char>, // older elements, eldest last.
+    //     younger: Vec<char> // younger elements, youngest last.
+    // }
+    //
+    // impl Queue {
+    //     /// Push a character onto the back of a queue.
+    //     pub fn push(&mut self, c: char) {
+    //         self.younger.push(c);
+    //     }
+    //
+    //     /// Pop a character off the front of a queue. Return `Some(c)` if there
+    //     /// was a character to pop, or `None` if the queue was empty.
+    //     pub fn pop(&mut self) -> Option<char> {
+    //         if self.older.is_empty() {
+    //             if self.younger.is_empty() {
+    //                 return None;
+    //             }
+    //
+    //             // Bring the elements in younger over to older, and put them in
+    //             // the promised order.
+    //             use std::mem::swap;
+    //             swap(&mut self.older, &mut self.younger);
+    //             self.older.reverse();
+    //         }
+    //
+    //         // Now older is guaranteed to have something. Vec's pop method
+    //         // already returns an Option, so we're set.
+    //         self.older.pop()
+    //     }
+    // }
 
     // An impl block is simply a collection of fn definitions, each of which becomes a method on the struct type named at the top of the block. Here we've defined a public struct Queue, and then given it two public methods, push and pop.
 
@@ -212,65 +1641,65 @@ fn main() {
     // In the example, the push and pop methods refer to the Queue's fields as self.older and self.younger.A Rust method must explicitly use self to refer to the value it was called on, similar to the way Python methods use self, and JS methods use this.
 
     // Since push and pop need to modify the Queue, they both take &mut self. However, when we call a method, we don't need to borrow the mutable ref ourself. The ordinary method call syntax takes care of the implicitly. So with these definitions in place, we can use Queue like this:
-    let mut q = Queue { older: Vec::new(), younger: Vec::new() };
-
-    q.push('0');
-    q.push('1');
-    assert_eq!(q.pop(), Some('0'));
-
-    q.push('∞');
-    assert_eq!(q.pop(), Some('1'));
-    assert_eq!(q.pop(), Some('∞'));
-    assert_eq!(q.pop(), None);
+    // let mut q = Queue { older: Vec::new(), younger: Vec::new() };
+    //
+    // q.push('0');
+    // q.push('1');
+    // assert_eq!(q.pop(), Some('0'));
+    //
+    // q.push('∞');
+    // assert_eq!(q.pop(), Some('1'));
+    // assert_eq!(q.pop(), Some('∞'));
+    // assert_eq!(q.pop(), None);
 
     // Simply writing q.push(...) borrows a mutable ref to q, as if we had written (&mut q).push(...), since that's what the push method's self requires.
 
     // If a method doesn't need to modify its self, then we can define it to take a shared ref instead. For example:
-    impl Queue {
-        pub fn is_empty(&self) -> bool {
-            self.older.is_empty() && self.younger.is_empty()
-        }
-    }
+    // impl Queue {
+    //     pub fn is_empty(&self) -> bool {
+    //         self.older.is_empty() && self.younger.is_empty()
+    //     }
+    // }
 
     // Again, the method call expression knows which sort of ref to borrow:
-    assert!(q.is_empty());
-    q.push('☉');
-    assert!(!q.is_empty());
+    // assert!(q.is_empty());
+    // q.push('☉');
+    // assert!(!q.is_empty());
 
     // Or, if a method wants to take ownership of self, it can take self by value:
-    impl Queue {
-        pub fn split(self) -> (Vec<char>, Vec<char>) {
-            (self.older, self.younger)
-        }
-    }
+    // impl Queue {
+    //     pub fn split(self) -> (Vec<char>, Vec<char>) {
+    //         (self.older, self.younger)
+    //     }
+    // }
 
     // Calling this split method looks like the other method calls:
-    let mut q = Queue { older: Vec::new(), younger: Vec::new() };
-
-    q.push('P');
-    q.push('D');
-    assert_eq!(q.pop(), Some('P'));
-    q.push('X');
-
-    let (older, younger) = q.split();
-    // q is now uninitialized
-    assert_eq!(older, vec!['D']);
-    assert_eq!(younger, vec!['X']);
+    // let mut q = Queue { older: Vec::new(), younger: Vec::new() };
+    //
+    // q.push('P');
+    // q.push('D');
+    // assert_eq!(q.pop(), Some('P'));
+    // q.push('X');
+    //
+    // let (older, younger) = q.split();
+    // // q is now uninitialized
+    // assert_eq!(older, vec!['D']);
+    // assert_eq!(younger, vec!['X']);
 
     // Since split takes its self by value, this moves the Queue out of q, leaving q uninitialized. Since split's self now owns the queue, it's able to move the individual vectors out of it, and return them to the caller.
 
     // We can also define methods that don't take self as an argument at all. These become functions associated with the struct type itself, not with any specific value of the type. Following the tradition established by C++ and Java, Rust calls these static methods. They're often used to provide constructor functions like so:
-    impl Queue {
-        pub fn new() -> {
-            Queue { older: Vec::new(), younger: Vec::new() }
-        }
-    }
+    // impl Queue {
+    //     pub fn new() -> {
+    //         Queue { older: Vec::new(), younger: Vec::new() }
+    //     }
+    // }
 
     // To use this method, we refer to it as Queue::new. The type name, a double colon, then the method name. Now our example code becomes a bit more svelte:
-    let mut q = Queue::new()
-
-    q.push('*');
-    ...
+    // let mut q = Queue::new()
+    //
+    // q.push('*');
+    // ...
 
     // It's conventional in Rust for constructor functions to be named new. We've already seen Vec::new, Box::new, HashMap::new, and others. But there's nothing special about the name new. It's not a keyword, and types often have other static methods that serve as constructors, like Vec::with_capacity.
 
@@ -283,36 +1712,42 @@ fn main() {
     // Our earlier definition of Queue is unsatisfying. It is written to store characters, but there's nothing about its structure or methods that is specific to characters at all. If we were to define another struct that held, say, String values, the code could be identical, except that char would be replaced with String. That would be a waste of time.
 
     // Fortunately, Rust structs can be generic, meaning that their definition is a template into which we can plug whatever types we like. For example, here's a definition for Queue that can hold values of any type:
-    pub struct Queue<T> {
-        older: Vec<T>,
-        younger: Vec<T>
-    }
+    // #[derive(Clone, Debug)]
+    // pub struct Queue<T> {
+    //     older: Vec<T>,
+    //     younger: Vec<T>,
+    //     max_len: Option<usize> // the most elements try_push will allow, if any
+    // }
 
     // We can read the <T> in Queue<T> as "for any element type T...". So this definition reads, "For any type T, a Queue<T> is two fields of type Vec<T>". For example, in Queue<String>, T is String, so older and younger have type Vec<String>. In Queue<char>, T is char, and we get a struct identical to the char-specific definition we started with. In fact, Vec itself is a generic struct, defined in just this way.
 
     // In generic struct definitions, the type names used in <angle brackets> are called type parameters. An impl block for a generic struct looks like this:
-    impl<T> Queue<T> {
-        pub fn new() -> Queue<T> {
-            Queue { older: Vec::new(), younger: Vec::new() }
-        }
-
-        pub fn push(&mut self, t: T) {
-            self.younger.push(t);
-        }
-
-        pub fn is_empty(&self) -> bool {
-            self.older.is_empty() && self.younger.is_empty()
-        }
-
-        ...
-    }
+    // impl<T> Queue<T> {
+    //     pub fn new() -> Queue<T> {
+    //         Queue { older: Vec::new(), younger: Vec::new(), max_len: None }
+    //     }
+    //
+    //     pub fn push(&mut self, t: T) {
+    //         self.younger.push(t);
+    //     }
+    //
+    //     pub fn len(&self) -> usize {
+    //         self.older.len() + self.younger.len()
+    //     }
+    //
+    //     pub fn is_empty(&self) -> bool {
+    //         self.len() == 0
+    //     }
+    //
+    //     ...
+    // }
 
     // We can read the line impl<T> Queue<T> as something like, "for any type T, here are some methods available on Queue<T>". Then, we can use the type parameter T as a type in the method definitions.
 
     // We've used Rust's shorthand for self parameters in the preceding code. Writing out Queue<T> everywhere becomes a mouthful and a distraction. As another shorthand, every impl block, generic or not, defines the special type parameter Self (note the CamelCase name) to be whatever type we're adding methods to. In the preceding code Self would be Queue<T>, so we can abbreviate Queue::new's def a bit further:
-    pub fn new() -> Self {
-        Queue { older: Vec::new(), younger: Vec::new() }
-    }
+    // pub fn new() -> Self {
+    //     Queue { older: Vec::new(), younger: Vec::new(), max_len: None }
+    // }
 
     // In the body of new, we didn't need to write the type parameter in the construction expression. Simply writing Queue { ... } was good enough. This is Rust's type inference at work. Since there's only one type that works for that function's return value, namely Queue<T>, Rust supplies the parameter for us. However, we'll always need to supply type parameters in function signatures and type definitions. Rust doesn't infer those. Instead, it uses those explicit types as the basis from which it infers types within function bodies.
 
@@ -332,60 +1767,726 @@ fn main() {
 
 
 
-    // Structs with Lifetime Parameters
+    // Growing Queue<T>
 
-    // As discussed in "Structs Containing References" in chapt 5, if a struct type contains references, we must name those references' lifetimes. For example, here's a structure that might hold references to the greatest and least elements of some slice:
-    struct Extrema<'elt> {
-        greatest: &'elt i32,
-        least: &'elt i32
+    // Our generic Queue<T> only knows how to push, pop, and tell us whether it's empty. Like GrayscaleMap, it's worth fleshing out with the operations we keep reaching for. The one thing to keep in mind is the two-vector layout: older holds the elements that will pop first, eldest last, and younger holds the rest in push order. So the logical "pop order" of a queue is older reversed, followed by younger.
+
+    // For validation passes, we sometimes want to accumulate a value over the queue while checking an invariant, and give up as soon as the check fails. Iterator's try_fold already knows how to stop at the first Err, so we just feed it the elements in pop order:
+    // In src/queue.rs: Queue::try_fold
+
+    // Summing with a running check succeeds when every element passes:
+    let mut q = Queue::new();
+    q.push(1);
+    q.push(2);
+    q.push(3);
+    assert_eq!(q.pop(), Some(1)); // moves 2 and 3 into older
+    q.push(4);
+
+    let total: Result<i32, String> = q.try_fold(0, |sum, &n| Ok(sum + n));
+    assert_eq!(total, Ok(9));
+
+    // But the first failure is returned, and nothing after it is visited:
+    let mut visited = Vec::new();
+    let result = q.try_fold(0, |sum, &n| {
+        visited.push(n);
+        if n % 2 == 1 { Err(format!("odd element {}", n)) } else { Ok(sum + n) }
+    });
+    assert_eq!(result, Err("odd element 3".to_string()));
+    assert_eq!(visited, vec![2, 3]);
+
+    // Sometimes we want to take back the most recent pushes, say, to undo a batch of operations. That means removing from the back of the queue, which is the mirror image of pop: the youngest elements are at the end of younger, and if younger runs dry, the rest are at the front of older. When that happens, we have to move elements from older over to younger, much as pop does in the other direction. That's the job of pop_back, below; here we just call it up to n times:
+    // In src/queue.rs: Queue::pop_back_n
+
+    // Taking fewer than are available leaves the rest poppable in their usual order:
+    let mut q = Queue::new();
+    for c in "abcde".chars() {
+        q.push(c);
+    }
+    assert_eq!(q.pop(), Some('a')); // everything left is now in older
+    assert_eq!(q.pop_back_n(2), vec!['e', 'd']);
+    assert_eq!(q.pop(), Some('b'));
+
+    // Taking exactly as many as are left empties the queue:
+    q.push('f');
+    assert_eq!(q.pop_back_n(2), vec!['f', 'c']);
+    assert!(q.is_empty());
+
+    // And asking for more than there are just returns what there is:
+    q.push('g');
+    q.push('h');
+    assert_eq!(q.pop_back_n(10), vec!['h', 'g']);
+    assert!(q.is_empty());
+
+    // When the pop order doesn't matter and we just want the contents in order, we can take the queue apart with split, put the two halves back together, and sort:
+    // In src/queue.rs: Queue::into_sorted_vec
+
+    let mut q = Queue::new();
+    for &n in &[42, 7, 19, 3, 88, 7] {
+        q.push(n);
+    }
+    q.pop();
+    q.push(55);
+    assert_eq!(q.into_sorted_vec(), vec![3, 7, 7, 19, 55, 88]);
+
+    // Replacing every occurrence of a value doesn't care about pop order at all, since nothing moves. We just walk both vectors and overwrite matches in place:
+    // In src/queue.rs: Queue::replace_all
+
+    let mut q = Queue::new();
+    for word in &["ok", "bad", "ok", "bad"] {
+        q.push(word.to_string());
+    }
+    assert_eq!(q.pop(), Some("ok".to_string())); // "bad", "ok", "bad" now in older
+    q.push("bad".to_string());
+    q.push("fine".to_string());
+
+    q.replace_all(&"bad".to_string(), "***".to_string());
+    let mut popped = Vec::new();
+    while let Some(word) = q.pop() {
+        popped.push(word);
+    }
+    assert_eq!(popped, vec!["***", "ok", "***", "***", "fine"]);
+
+    // Removing a whole range of elements by their position in pop order takes a little more care, since the range may fall in older, in younger, or straddle the two. Logical index i lives at older[older.len() - 1 - i] while i is less than older.len(), and at younger[i - older.len()] after that. So we translate the part of the range that falls in each vector separately, remembering that older's part comes out backwards. Like slice ranges, a bad range is a bug in the caller, so we panic:
+    // In src/queue.rs: Queue::drain_range
+
+    // We'll use a helper to build a queue holding 0 through 9, with 0 through 4 in older and the rest in younger, and another to pop everything that's left:
+    fn split_queue() -> Queue<i32> {
+        let mut q = Queue::new();
+        for n in -1..5 {
+            q.push(n);
+        }
+        q.pop(); // moves 0..5 into older
+        for n in 5..10 {
+            q.push(n);
+        }
+        q
+    }
+
+    fn drain_all<T>(q: &mut Queue<T>) -> Vec<T> {
+        let mut all = Vec::new();
+        while let Some(t) = q.pop() {
+            all.push(t);
+        }
+        all
+    }
+
+    // Draining from the front, from the middle of younger, and across the boundary between the two:
+    let mut q = split_queue();
+    assert_eq!(q.drain_range(0, 3), vec![0, 1, 2]);
+    assert_eq!(drain_all(&mut q), vec![3, 4, 5, 6, 7, 8, 9]);
+
+    let mut q = split_queue();
+    assert_eq!(q.drain_range(6, 8), vec![6, 7]);
+    assert_eq!(drain_all(&mut q), vec![0, 1, 2, 3, 4, 5, 8, 9]);
+
+    let mut q = split_queue();
+    assert_eq!(q.drain_range(3, 7), vec![3, 4, 5, 6]);
+    assert_eq!(drain_all(&mut q), vec![0, 1, 2, 7, 8, 9]);
+
+    // An empty range removes nothing:
+    let mut q = split_queue();
+    assert!(q.drain_range(5, 5).is_empty());
+    assert_eq!(drain_all(&mut q).len(), 10);
+
+    // To edit the element at the front of the queue in place, say, to decrement a retry counter before deciding whether to pop it, we need a mutable ref to whatever pop would return next. That's the last element of older, so if older is empty we do the same rebalancing pop does first:
+    // In src/queue.rs: Queue::peek_mut
+
+    // With everything still in younger, peek_mut moves it over and hands back the eldest:
+    let mut q = Queue::new();
+    q.push(3);
+    q.push(5);
+    *q.peek_mut().unwrap() -= 1;
+    assert_eq!(q.pop(), Some(2));
+
+    // When the front already lives in older, nothing moves:
+    q.push(7);
+    if let Some(retries) = q.peek_mut() {
+        *retries = 0;
+    }
+    assert_eq!(q.pop(), Some(0));
+    assert_eq!(q.pop(), Some(7));
+    assert_eq!(q.peek_mut(), None);
+
+    // For computing differences between neighbors, it's handy to walk the queue in consecutive pairs, much like a slice's windows(2), but handing back tuples. Zipping the pop-order sequence with itself shifted by one does the job, and crosses from older into younger without any special handling:
+    // In src/queue.rs: Queue::pairs
+
+    let mut q = Queue::new();
+    q.push(0);
+    q.push(1);
+    q.push(2);
+    q.pop(); // 1 and 2 are now in older
+    q.push(3);
+    let pairs: Vec<(&i32, &i32)> = q.pairs().collect();
+    assert_eq!(pairs, vec![(&1, &2), (&2, &3)]);
+
+    // With fewer than two elements, there are no pairs at all:
+    let mut lonely = Queue::new();
+    assert_eq!(lonely.pairs().count(), 0);
+    lonely.push('x');
+    assert_eq!(lonely.pairs().count(), 0);
+
+    // To promote an urgent element so that it pops next, we find the first one in pop order that matches, pull it out, and push it onto the end of older, which is exactly where pop looks first. Since older is stored backwards, the first match in pop order is the last match in older, so we search older from the end before moving on to younger:
+    // In src/queue.rs: Queue::promote
+
+    // We'll use split_queue from above, with 0 through 4 in older and 5 through 9 in younger. Promoting from younger:
+    let mut q = split_queue();
+    assert!(q.promote(|&n| n > 6));
+    assert_eq!(drain_all(&mut q), vec![7, 0, 1, 2, 3, 4, 5, 6, 8, 9]);
+
+    // Promoting from older:
+    let mut q = split_queue();
+    assert!(q.promote(|&n| n % 3 == 2));
+    assert_eq!(drain_all(&mut q), vec![2, 0, 1, 3, 4, 5, 6, 7, 8, 9]);
+
+    // When nothing matches, the queue is left as it was:
+    let mut q = split_queue();
+    assert!(!q.promote(|&n| n > 100));
+    assert_eq!(drain_all(&mut q), (0..10).collect::<Vec<i32>>());
+
+    // Several of the methods above walk the queue in pop order by chaining older, reversed, onto younger. That's worth packaging up as an iterator type of its own, which is how the standard collections do it: iter() borrows the queue, and IntoIterator consumes it. Since the queue always knows exactly how many elements it holds, both iterators can report an exact size_hint and implement ExactSizeIterator. A plain Chain can't do that, because adding two lengths might overflow, but our two vectors' lengths can't add up to more than fits in memory:
+    // In src/queue.rs:
+    //     Iter
+    //     impl Iterator for Iter<'a, T>
+    //     impl ExactSizeIterator for Iter<'a, T>
+    //     IntoIter
+    //     impl Iterator for IntoIter<T>
+    //     impl ExactSizeIterator for IntoIter<T>
+    //     Queue::iter
+    //     impl IntoIterator for &'a Queue<T>
+    //     impl IntoIterator for Queue<T>
+
+    // Both iterators count down as they go, across the boundary between older and younger:
+    let q = split_queue();
+    let mut iter = q.iter();
+    assert_eq!(iter.len(), 10);
+    assert_eq!(iter.nth(5), Some(&5));
+    assert_eq!(iter.size_hint(), (4, Some(4)));
+
+    let mut into_iter = split_queue().into_iter();
+    assert_eq!(into_iter.len(), 10);
+    assert_eq!(into_iter.next(), Some(0));
+    assert_eq!(into_iter.size_hint(), (9, Some(9)));
+
+    // So adapters that preallocate, like collect, know exactly how much room to make:
+    let mapped = q.iter().map(|&n| n * 2);
+    assert_eq!(mapped.size_hint(), (10, Some(10)));
+    let doubled: Vec<i32> = mapped.collect();
+    assert_eq!(doubled, vec![0, 2, 4, 6, 8, 10, 12, 14, 16, 18]);
+
+    let owned: Vec<i32> = split_queue().into_iter().collect();
+    assert_eq!(owned, (0..10).collect::<Vec<i32>>());
+    assert_eq!((&q).into_iter().len(), 10);
+
+    // Sometimes a queue must not grow without limit; a producer that gets too far ahead of its consumer should be told to back off. We can wrap a Queue in a struct that also knows its capacity, and have push hand the item back when there's no room. Rather than a bare Result<(), T>, it's nicer to return a dedicated error type carrying the rejected item. If that type implements the standard Error trait, callers can propagate it with the ? operator like any other error. Implementing Error requires Debug and Display:
+    use std::error::Error;
+    use std::fmt;
+
+    /// A `Queue` that holds at most a fixed number of elements.
+    pub struct BoundedQueue<T> {
+        queue: Queue<T>,
+        capacity: usize
+    }
+
+    /// The error returned when pushing onto a full `BoundedQueue`. It owns the
+    /// rejected item, so nothing is lost.
+    #[derive(Debug)]
+    pub struct QueueFull<T>(pub T);
+
+    impl<T> QueueFull<T> {
+        /// Recover the item that couldn't be pushed.
+        pub fn into_inner(self) -> T {
+            self.0
+        }
+    }
+
+    impl<T> fmt::Display for QueueFull<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "queue is full")
+        }
+    }
+
+    impl<T: fmt::Debug> Error for QueueFull<T> {}
+
+    impl<T> BoundedQueue<T> {
+        /// Create an empty queue that will hold at most `capacity` elements.
+        pub fn new(capacity: usize) -> BoundedQueue<T> {
+            BoundedQueue { queue: Queue::new(), capacity }
+        }
+
+        /// Push `t` onto the back of the queue, or return it inside a
+        /// `QueueFull` error if the queue is already at capacity.
+        pub fn checked_push(&mut self, t: T) -> Result<(), QueueFull<T>> {
+            if self.queue.len() >= self.capacity {
+                return Err(QueueFull(t));
+            }
+            self.queue.push(t);
+            Ok(())
+        }
+
+        /// Pop an element off the front of the queue.
+        pub fn pop(&mut self) -> Option<T> {
+            self.queue.pop()
+        }
     }
 
-    // Earlier, we said to think of a declaration like struct Queue<T> as meaning that, given any specific type T, we can make a Queue<T> that holds that type. Similarly, we can think of struct Extrema<'elt> as meaning that, given any specific lifetime 'elt, you can make an Extrema<'elt> that holds references with that lifetime.
+    // Pushing up to the limit succeeds, and the next push fails, handing the item back:
+    let mut bounded = BoundedQueue::new(2);
+    assert!(bounded.checked_push("first").is_ok());
+    assert!(bounded.checked_push("second").is_ok());
+    let err = bounded.checked_push("third").unwrap_err();
+    assert_eq!(err.to_string(), "queue is full");
+    assert_eq!(err.into_inner(), "third");
+
+    // Popping makes room again:
+    assert_eq!(bounded.pop(), Some("first"));
+    assert!(bounded.checked_push("third").is_ok());
+
+    // And QueueFull works with ?:
+    fn enqueue_all(q: &mut BoundedQueue<i32>, items: &[i32]) -> Result<(), Box<dyn Error>> {
+        for &item in items {
+            q.checked_push(item)?;
+        }
+        Ok(())
+    }
+    let mut bounded = BoundedQueue::new(3);
+    assert!(enqueue_all(&mut bounded, &[1, 2]).is_ok());
+    assert!(enqueue_all(&mut bounded, &[3, 4]).is_err());
+
+    // Like slice::split_first, we can split a queue into its front element and everything else. Since this takes the queue by value, the caller gets the head by value too, with no separate pop and no juggling of ownership:
+    // In src/queue.rs: Queue::split_first
+
+    let (head, mut tail) = split_queue().split_first().unwrap();
+    assert_eq!(head, 0);
+    assert_eq!(drain_all(&mut tail), (1..10).collect::<Vec<i32>>());
+
+    assert!(Queue::<i32>::new().split_first().is_none());
+
+    // Vec's retain keeps only the elements a predicate approves of. A position-aware version for the queue passes the predicate each element's index in pop order as well, which makes it easy to keep every nth element or drop a particular position. To visit the elements in pop order, we briefly turn older around, so that it runs front to back like younger does:
+    // In src/queue.rs: Queue::retain_indexed
 
-    // Here's a function to scan a slice and return an Extrema value whose fields refer to its elements:
-    fn find_extrema<'s>(slice: &'s [i32]) -> Extrema<'s> {
-        let mut greatest = &slice[0];
-        let mut least = &slice[0];
+    let mut q = split_queue();
+    let mut seen = Vec::new();
+    q.retain_indexed(|i, &n| {
+        seen.push(n);
+        i % 2 == 0
+    });
+    assert_eq!(seen, (0..10).collect::<Vec<i32>>());
+    assert_eq!(drain_all(&mut q), vec![0, 2, 4, 6, 8]);
+
+    // Unlike peek_mut, a plain peek can't rebalance the queue, since it only has a shared ref. It doesn't need to: if older has anything, its last element is the front, and otherwise the front is the first element of younger:
+    // In src/queue.rs: Queue::peek
+
+    // With everything in younger:
+    let mut q = Queue::new();
+    assert_eq!(q.peek(), None);
+    q.push('a');
+    q.push('b');
+    assert_eq!(q.peek(), Some(&'a'));
+
+    // And with the front in older:
+    assert_eq!(q.pop(), Some('a'));
+    q.push('c');
+    assert_eq!(q.peek(), Some(&'b'));
+    assert_eq!(q.pop(), Some('b'));
+
+    // Since it's often handy to know exactly how many elements a queue holds, we've added a len method to the generic impl block above, alongside is_empty. The count is simply the sum of the two vectors' lengths. is_empty is now written in terms of len, so the two can never disagree:
+    let mut q = Queue::new();
+    q.push("one");
+    q.push("two");
+    q.push("three");
+    q.pop();
+    assert_eq!(q.len(), 2);
+    assert!(!q.is_empty());
+
+    // To build a queue from existing data, it's natural to collect an iterator into one, and to extend an existing queue with more elements. Both come down to appending to younger, which keeps the iterator's order as push order, so the first item produced is the first one popped. (In the 2018 edition, FromIterator isn't in the prelude yet, so we have to import it.)
+    // In src/queue.rs:
+    //     impl FromIterator<T> for Queue<T>
+    //     impl Extend<T> for Queue<T>
+
+    let mut q: Queue<i32> = (0..5).collect();
+    assert_eq!(q.pop(), Some(0));
+    q.extend(vec![5, 6]);
+    assert_eq!(q.into_iter().collect::<Vec<i32>>(), vec![1, 2, 3, 4, 5, 6]);
+
+    // Here's pop_back itself, which turns our queue into a proper double-ended queue. It's the mirror image of pop: the youngest element is the last one in younger, and if younger is empty, we move elements over from older, reversing them so that the youngest comes last. And as with peek, peek_back can find the youngest element without moving anything, since if younger is empty, it's the first element of older:
+    // In src/queue.rs: Queue::pop_back, Queue::peek_back
+
+    // Mixing pushes and pops from both ends, the queue never loses track of which end is which:
+    let mut q = Queue::new();
+    q.push(1);
+    q.push(2);
+    q.push(3);
+    assert_eq!(q.peek_back(), Some(&3));
+    assert_eq!(q.pop_back(), Some(3));
+    assert_eq!(q.pop(), Some(1)); // moves 2 into older
+    q.push(4);
+    assert_eq!(q.pop_back(), Some(4));
+    assert_eq!(q.peek_back(), Some(&2)); // younger is empty, so this is in older
+    assert_eq!(q.pop_back(), Some(2)); // moves older back over to younger
+    q.push(5);
+    q.push(6);
+    assert_eq!(q.pop(), Some(5));
+    assert_eq!(q.pop_back(), Some(6));
+    assert_eq!(q.pop_back(), None);
+    assert_eq!(q.peek_back(), None);
+
+    // Queue<T> now derives Clone and Debug (see its definition above), which is all we need to clone queues and print them in tests. Default and PartialEq are better written by hand, though. Deriving Default would require T: Default, since the derive macro doesn't know that an empty queue doesn't need any T values at all. And the derived PartialEq would compare older with older and younger with younger, so two queues holding the same elements in the same order could compare unequal just because one of them had been popped from more recently. Comparing the elements in pop order gets it right:
+    // In src/queue.rs:
+    //     impl Default for Queue<T>
+    //     impl PartialEq for Queue<T>
+    //     impl Eq for Queue<T>
+
+    // Two queues with the same contents, one with everything in older and the other split between older and younger, are equal:
+    let mut a: Queue<i32> = (0..4).collect();
+    a.pop();
+    let mut b: Queue<i32> = (-1..2).collect();
+    b.pop();
+    b.pop();
+    b.push(2);
+    b.push(3);
+    assert_eq!(a, b);
+    assert_ne!(a, (1..3).collect());
+
+    let c = a.clone();
+    assert_eq!(c, b);
+    assert_eq!(Queue::<String>::default(), Queue::new());
+    assert_eq!(format!("{:?}", Queue::<char>::default()), "Queue { older: [], younger: [], max_len: None }");
+
+    // In a hot producer loop, we'd rather not have the queue reallocating as it grows. Pushes always land in younger, so that's the vector reserve should grow. with_capacity is a bit subtler: whenever pop finds older empty, it swaps the two vectors, so each one takes its turn as younger. We split the requested capacity between them, giving younger the larger half since it's the one being pushed onto first. And when a queue has drained, shrink_to_fit hands the memory back. capacity reports the two vectors' room between them:
+    // In src/queue.rs: Queue::with_capacity, Queue::capacity, Queue::reserve, Queue::shrink_to_fit
+
+    let q: Queue<u64> = Queue::with_capacity(9);
+    assert!(q.capacity() >= 9);
+
+    // Pushing up to the reserved amount never grows the queue:
+    let mut q = Queue::new();
+    q.reserve(1000);
+    let capacity = q.capacity();
+    assert!(capacity >= 1000);
+    for i in 0..1000 {
+        q.push(i);
+    }
+    assert_eq!(q.capacity(), capacity);
+
+    while q.pop().is_some() {}
+    q.shrink_to_fit();
+    assert_eq!(q.capacity(), 0);
+
+    // clear empties a queue but, like Vec::clear, keeps its allocations for reuse. append moves all of another queue's elements onto the back of this one. Everything already in self must pop first, so the newcomers all go into younger, in other's pop order: its older elements backwards, then its younger ones:
+    // In src/queue.rs: Queue::clear, Queue::append
+
+    let mut front = split_queue();
+    let mut back: Queue<i32> = (9..13).collect();
+    back.pop();
+    back.push(13);
+    front.append(&mut back);
+    assert!(back.is_empty());
+    assert_eq!(front, (0..14).collect());
+
+    // Appending an empty queue changes nothing:
+    let before = front.clone();
+    front.append(&mut Queue::new());
+    assert_eq!(front, before);
+
+    let capacity = front.capacity();
+    front.clear();
+    assert!(front.is_empty());
+    assert_eq!(front.capacity(), capacity);
+
+    // Checking membership only makes sense for element types that can be compared, so contains goes in its own impl block with a T: PartialEq bound. The method simply isn't there for a Queue of some type that can't be compared. Order doesn't matter, so we look in both vectors as they are:
+    // In src/queue.rs: Queue::contains
+
+    let mut q: Queue<char> = "xyz".chars().collect();
+    q.pop(); // 'y' and 'z' move into older
+    q.push('w');
+    assert!(q.contains(&'y') && q.contains(&'w'));
+    assert!(!q.contains(&'x'));
+
+    let mut names: Queue<String> = Queue::new();
+    names.push("Hokey".to_string());
+    names.pop();
+    names.push("Pokey".to_string());
+    names.push("Smokey".to_string());
+    names.pop(); // "Smokey" is now in older
+    names.push("Jokey".to_string());
+    assert!(names.contains(&"Smokey".to_string()));
+    assert!(names.contains(&"Jokey".to_string()));
+    assert!(!names.contains(&"Hokey".to_string()));
+
+    // How expensive is all this moving between vectors? For a queue used as a queue, with only push and pop, each element is pushed onto younger once, moved over to older at most once, and popped from older once, so although an individual pop can take time proportional to the length of the queue, the cost averaged over any sequence of operations is constant. This is called amortized O(1) time.
+
+    // pop_back threatens that guarantee. If it moved all of older over to younger the way pop moves younger to older, then alternating pop and pop_back on a long queue would shuttle every element back and forth on every single call. That's why pop_back moves only the younger half: after one expensive rebalance, both ends have about half the elements, and it takes that many cheap operations to empty either one again.
+
+    // The two-vector layout is subtle enough that it's worth checking against a known-good implementation. The standard library's VecDeque is a double-ended queue too, so we can run a long random sequence of operations on both and make sure they agree at every step. We don't need a real random number generator for this; a simple xorshift generator gives us a reproducible sequence:
+    use std::collections::VecDeque;
+
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next_random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
 
-        for i in 1..slice.len() {
-            if slice[i] < *least    { least     = &slice[i]; }
-            if slice[i] > *greatest { greatest = &slice[i]; }
+    let mut q = Queue::new();
+    let mut reference = VecDeque::new();
+    for i in 0..100_000 {
+        match next_random() % 10 {
+            0..=4 => {
+                q.push(i);
+                reference.push_back(i);
+            }
+            5..=7 => assert_eq!(q.pop(), reference.pop_front()),
+            _ => assert_eq!(q.pop_back(), reference.pop_back())
         }
+        assert_eq!(q.len(), reference.len());
+        assert_eq!(q.peek(), reference.front());
+        assert_eq!(q.peek_back(), reference.back());
+    }
+    assert!(q.iter().eq(reference.iter()));
+
+    // BoundedQueue wraps a queue to give it a fixed capacity. For a streaming workload, it's convenient to have the limit on Queue itself, where it can be changed as the load changes. So Queue<T> now has a third field, max_len, which is None in every queue our constructors create. try_push refuses to grow the queue past the limit, handing the value back in the Err. Plain push still always succeeds; it's up to the caller to decide which one they want. Lowering the limit below the current length doesn't throw anything away, it just makes try_push fail until enough elements have been popped:
+    // In src/queue.rs: Queue::set_max_len, Queue::try_push
 
-        Extrema { greatest, least }
+    let mut q = Queue::new();
+    assert_eq!(q.try_push('a'), Ok(()));
+    q.set_max_len(Some(3));
+    assert_eq!(q.try_push('b'), Ok(()));
+    assert_eq!(q.try_push('c'), Ok(()));
+    assert_eq!(q.try_push('d'), Err('d'));
+    assert_eq!(q.len(), 3);
+
+    // Popping makes room, and removing the limit lifts it entirely:
+    q.pop();
+    assert_eq!(q.try_push('d'), Ok(()));
+    assert_eq!(q.try_push('e'), Err('e'));
+    q.set_max_len(None);
+    assert_eq!(q.try_push('e'), Ok(()));
+
+    // A Queue<T> needs &mut self to push or pop, so to share one between threads we put it behind a Mutex, and put the Mutex behind an Arc so each thread can own a handle. A consumer that finds the queue empty shouldn't spin; instead it waits on a Condvar, which atomically releases the lock while it sleeps, and push notifies the Condvar after adding an element. Condvar::wait can wake spuriously, so pop_wait checks the queue again in a loop each time it wakes. Deriving Clone would require T: Clone, but cloning a handle only clones the Arc, so we write the impl ourselves:
+    use std::sync::{Arc, Condvar, Mutex};
+
+    /// A handle to a `Queue` shared between threads. Cloning the handle
+    /// gives another handle to the same queue.
+    pub struct SharedQueue<T> {
+        inner: Arc<(Mutex<Queue<T>>, Condvar)>
     }
 
+    impl<T> Clone for SharedQueue<T> {
+        fn clone(&self) -> SharedQueue<T> {
+            SharedQueue { inner: Arc::clone(&self.inner) }
+        }
+    }
+
+    impl<T> SharedQueue<T> {
+        pub fn new() -> SharedQueue<T> {
+            SharedQueue { inner: Arc::new((Mutex::new(Queue::new()), Condvar::new())) }
+        }
+
+        /// Push `t` onto the back of the queue, waking one thread blocked in
+        /// `pop_wait`, if any.
+        pub fn push(&self, t: T) {
+            let (queue, available) = &*self.inner;
+            queue.lock().unwrap().push(t);
+            available.notify_one();
+        }
+
+        /// Pop the front element, or return `None` at once if the queue is
+        /// empty.
+        pub fn pop(&self) -> Option<T> {
+            self.inner.0.lock().unwrap().pop()
+        }
+
+        /// Pop the front element, blocking until one is available.
+        pub fn pop_wait(&self) -> T {
+            let (queue, available) = &*self.inner;
+            let mut queue = queue.lock().unwrap();
+            loop {
+                if let Some(t) = queue.pop() {
+                    return t;
+                }
+                queue = available.wait(queue).unwrap();
+            }
+        }
+    }
+
+    let shared = SharedQueue::new();
+    let consumer = {
+        let shared = shared.clone();
+        std::thread::spawn(move || (0..1000).map(|_| shared.pop_wait()).collect::<Vec<i32>>())
+    };
+    let producer = {
+        let shared = shared.clone();
+        std::thread::spawn(move || {
+            for i in 0..1000 {
+                shared.push(i);
+            }
+        })
+    };
+    producer.join().unwrap();
+    assert_eq!(consumer.join().unwrap(), (0..1000).collect::<Vec<_>>());
+    assert_eq!(shared.pop(), None);
+
+    // The ordinary retain is retain_indexed with the index ignored. Its complement, drain_filter, removes the elements a predicate selects and hands them back, in pop order. Since it has to look at every element anyway, it's simplest to take both vectors, walk them in pop order, and push each survivor onto the now-empty younger, which leaves them in the same order they started in:
+    // In src/queue.rs: Queue::retain, Queue::drain_filter
+
+    let mut q = split_queue();
+    q.retain(|&n| n % 2 == 0);
+    assert_eq!(drain_all(&mut q), vec![0, 2, 4, 6, 8]);
+
+    let mut q = split_queue();
+    assert_eq!(q.drain_filter(|&n| n % 3 == 0), vec![0, 3, 6, 9]);
+    assert_eq!(drain_all(&mut q), vec![1, 2, 4, 5, 7, 8]);
+
+    // Consuming a queue to build one of another element type is easy with the owning iterator, which already yields the elements in pop order: we collect the results straight into younger. That way f sees the elements in pop order too, which matters if it has side effects. The new queue keeps the old one's length limit:
+    // In src/queue.rs: Queue::map
+
+    let mut q = Queue::new();
+    q.push(1);
+    q.push(2);
+    q.push(3);
+    let mut tens = q.map(|x| x * 10);
+    assert_eq!((tens.pop(), tens.pop(), tens.pop()), (Some(10), Some(20), Some(30)));
+
+    let mut words = Queue::new();
+    words.push("quince".to_string());
+    words.push("fig".to_string());
+    let mut lengths = words.map(|s| s.len());
+    assert_eq!(drain_all(&mut lengths), vec![6, 3]);
+
+    assert_eq!(drain_all(&mut split_queue().map(|n| n + 1)), (1..11).collect::<Vec<i32>>());
+
+    // For batching, we want to take a fixed number of elements off the front all at once. Note that this split_off is the opposite of Vec's, which keeps the front and returns the back; ours is the dual of append. The first elements in pop order are the end of older, followed by the start of younger, and Vec::split_off can cut off either piece without moving anything else. Asking for more elements than there are just takes them all:
+    // In src/queue.rs: Queue::split_off
+
+    let mut q: Queue<i32> = (0..10).collect();
+    let mut front = q.split_off(3);
+    assert_eq!(drain_all(&mut front), vec![0, 1, 2]);
+    assert_eq!(drain_all(&mut q), (3..10).collect::<Vec<i32>>());
+
+    // Cutting within older, across the boundary, and past the end:
+    let mut q = split_queue();
+    assert_eq!(q.split_off(2), (0..2).collect());
+    assert_eq!(q.split_off(5), (2..7).collect());
+    assert_eq!(q.split_off(100), (7..10).collect());
+    assert!(q.is_empty());
+
+    // Like VecDeque, our queue keeps its elements in two pieces, so it can't hand out a single slice of its contents without first rearranging them. make_contiguous does that. It might seem natural to gather everything into older, but older is stored back to front, with the next element to pop at its end, so a slice of it would run in the wrong order. Instead we reverse older onto the front of younger, which is stored front to back. Afterwards older is empty, and the next pop will refill it from younger as usual, so the queue behaves just as it did before:
+    // In src/queue.rs: Queue::make_contiguous
+
+    let mut q = split_queue();
+    assert_eq!(q.make_contiguous(), &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    assert!(q.clone().split().0.is_empty());
+    assert_eq!(q.pop(), Some(0));
+    q.push(10);
+    assert_eq!(q.make_contiguous(), &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+    assert_eq!(drain_all(&mut q), (1..11).collect::<Vec<i32>>());
+
+    // For a moving average over a window of samples, we push each new sample, pop the oldest, and ask for the mean. Methods that do arithmetic on the elements need a bound saying the elements are numbers; Into<f64> covers f64 and f32 and all the integer types that convert to f64 without loss, and Copy lets us convert each one without taking it out of the queue. The order doesn't matter for a sum, so we could add up older and younger directly, but iter visits both just as well:
+    // In src/queue.rs: + Copy> Queue::sum, + Copy> Queue::average
+
+    let mut samples: Queue<f64> = vec![1.0, 2.0, 3.0].into_iter().collect();
+    assert_eq!(samples.sum(), 6.0);
+    assert_eq!(samples.average(), Some(2.0));
+
+    // Sliding the window along:
+    samples.pop();
+    samples.push(7.0);
+    assert_eq!(samples.average(), Some(4.0));
+
+    assert_eq!(split_queue().average(), Some(4.5));
+    assert_eq!(Queue::<u8>::new().sum(), 0.0);
+    assert_eq!(Queue::<u8>::new().average(), None);
+
+
+
+    // Structs with Lifetime Parameters
+
+    // As discussed in "Structs Containing References" in chapt 5, if a struct type contains references, we must name those references' lifetimes. For example, here's a structure that might hold references to the greatest and least elements of some slice:
+    // struct Extrema<'elt, T> {
+    //     greatest: &'elt T,
+    //     least: &'elt T
+    // }
+
+    // Earlier, we said to think of a declaration like struct Queue<T> as meaning that, given any specific type T, we can make a Queue<T> that holds that type. Similarly, we can think of struct Extrema<'elt, T> as meaning that, given any specific lifetime 'elt and element type T, you can make an Extrema<'elt, T> that holds references to T values with that lifetime.
+
+    // Here's a function to scan a slice and return an Extrema value whose fields refer to its elements. It works for any element type that can be ordered, and returns None for an empty slice, which has no greatest or least element:
+    // fn find_extrema<'s, T: Ord>(slice: &'s [T]) -> Option<Extrema<'s, T>> {
+    //     let (first, rest) = slice.split_first()?;
+    //     let mut greatest = first;
+    //     let mut least = first;
+    //
+    //     for x in rest {
+    //         if x < least    { least     = x; }
+    //         if x > greatest { greatest = x; }
+    //     }
+    //
+    //     Some(Extrema { greatest, least })
+    // }
+
     // Since find_extrema borrows elements of slice, which has lifetime 's, the Extrema struct we return also uses 's as the lifetime of its references. Rust always infers lifetime parameters for calls, so calls to find_extrema needn't mention them:
     let a = [0, -3, 0, 15, 48];
-    let e = find_extrema(&a);
+    let e = find_extrema(&a).unwrap();
     assert_eq!(*e.least, -3);
     assert_eq!(*e.greatest, 48);
 
     // Because it's so common for the return type to use the same lifetime as an argument, Rust lets us omit the lifetimes when there's on obvious candidate. We could also have written find_extrema's signature like so, with no change in meaning:
-    fn find_extrema(slice: &[i32]) -> Extrema {
-        ...
-    }
+    // fn find_extrema<T: Ord>(slice: &[T]) -> Option<Extrema<T>> {
+    //     ...
+    // }
 
     // Granted, we might have meant Extrema<'static>, but that's pretty unusual. Rust provides a shorthand for the common case.
 
 
 
+    // Putting Extrema to Work
+
+    // Since find_extrema is generic, it works just as well on strings, which are ordered lexicographically:
+    let words = ["pear", "apple", "quince", "fig"];
+    let e = find_extrema(&words).unwrap();
+    assert_eq!(*e.least, "apple");
+    assert_eq!(*e.greatest, "quince");
+
+    let empty: [i32; 0] = [];
+    assert!(find_extrema(&empty).is_none());
+
+    // Often we want to know not just what the extremes are but where they are. find_extrema_indexed makes the same single pass, but also returns the indices of the greatest and least elements, in that order. When several elements tie for greatest or least, the first occurrence wins; the strict comparisons mean a later equal element never replaces an earlier one:
+    // In src/extrema.rs: find_extrema_indexed
+
+    let a = [7, 48, -3, 48, 0, -3];
+    let (e, greatest_index, least_index) = find_extrema_indexed(&a).unwrap();
+    assert_eq!((*e.greatest, greatest_index), (48, 1));
+    assert_eq!((*e.least, least_index), (-3, 2));
+    assert!(find_extrema_indexed(&empty).is_none());
+
+    // For numeric slices, the difference between the extremes is often what we're after. An impl block can be specific to one instantiation of a generic type, so these methods exist only on Extrema<i32>. Since greatest - least overflows when the values straddle zero near the ends of i32's range, range panics in that case in debug builds, and checked_range returns None instead:
+    // In src/extrema.rs: Extrema::range, Extrema::checked_range, Extrema::contains
+
+    let e = find_extrema(&[-3, 0, 15, 48]).unwrap();
+    assert_eq!(e.range(), 51);
+    assert_eq!(e.checked_range(), Some(51));
+    assert!(e.contains(&-3) && e.contains(&48) && !e.contains(&49));
+    assert_eq!(find_extrema(&[i32::MIN, i32::MAX]).unwrap().checked_range(), None);
+
+
+
     // Deriving Common Traits for Struct Types
 
     // Structs can be very easy to write:
-    struct Point {
-        x: f64,
-        y: f64
-    }
+    // struct Point {
+    //     x: f64,
+    //     y: f64
+    // }
 
     // However, if we were to start using this Point type, we would quickly notice that it's a bit of a pain. As written, Point is not copyable or cloneable. We can't print it with println!("{:?}", point), and it doesn't support the == and != operators.
 
     // Each of these features has a name in Rust, Copy, Clone, Debug, and PartialEq. They are called traits. Chapt 11, goes into more detail on how to implement traits by hand for our own structs. But in the case of these standard traits, and several others, we don't need to implement them by hand unless we want some kind of custom behaviour. Rust can automatically implement them for us, with mechanical accuracy. Just add a #[derive] attribute to the struct:
-    #[derive(Copy, Clone, Debug, PartialEq)]
-    struct Point {
-        x: f64,
-        y: f64
-    }
+    // #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    // #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    // struct Point {
+    //     x: f64,
+    //     y: f64
+    // }
 
     // Each of these traits can be implemented automatically for a struct, provided that each of its fields implements the trait. We can ask Rust to derive PartialEq for Point because its two fields are both of type f64, which already implements PartialEq.
 
@@ -395,6 +2496,185 @@ fn main() {
 
 
 
+    // Point as a 2-D Vector
+
+    // With Copy, Clone, Debug, and PartialEq derived, Point is pleasant enough to pass around, but it still has no geometry. The distance between two points is the length of the hypotenuse of a right triangle whose legs are the differences in x and y. We could compute sqrt(dx * dx + dy * dy) ourselves, but f64's hypot method does the same thing without overflowing or underflowing in the intermediate squares. An associated const gives us a name for the origin; a struct expression whose fields are all constants is itself a constant expression, so this is allowed:
+    // In src/point.rs: Point::distance, Point::magnitude
+
+    let p = Point { x: 3.0, y: 4.0 };
+    assert_eq!(p.magnitude(), 5.0);
+    assert_eq!(p.distance(&Point { x: 6.0, y: 8.0 }), 5.0);
+    assert_eq!(Point::ORIGIN.magnitude(), 0.0);
+    assert_eq!(Point { x: 1e300, y: 1e300 }.magnitude(), 1e300 * 2f64.sqrt());
+
+    // To use Point as a vector for physics, we want the arithmetic operators. Each operator is a trait in std::ops; Add and Mul are already in scope from the Bounds section. Because Point is Copy, taking self and other by value costs nothing and leaves the caller's points usable afterwards:
+    // In src/point.rs:
+    //     impl Add for Point
+    //     impl Sub for Point
+    //     impl Neg for Point
+    //     impl Mul<f64> for Point
+    //     impl Div<f64> for Point
+    //     impl AddAssign for Point
+    //     impl SubAssign for Point
+    //     Point::dot
+
+    let a = Point { x: 0.1, y: -2.7 };
+    let b = Point { x: 3.3, y: 0.2 };
+    assert!((a - b + b).distance(&a) < 1e-12);
+    assert_eq!(-a + a, Point::ORIGIN);
+    assert_eq!(b * 2.0 / 2.0, b);
+    assert_eq!(Point { x: 1.0, y: 2.0 }.dot(&Point { x: 3.0, y: -4.0 }), -5.0);
+
+    let mut pos = Point::ORIGIN;
+    pos += Point { x: 1.0, y: 1.0 };
+    pos -= Point { x: 0.5, y: 2.0 };
+    assert_eq!(pos, Point { x: 0.5, y: -1.0 });
+
+    // Rotating a point about the origin by θ multiplies it by the rotation matrix [cos θ, -sin θ; sin θ, cos θ]. Linear interpolation is just the operators above put to work; we deliberately don't clamp t, so values outside 0..=1 extrapolate along the same line:
+    // In src/point.rs: Point::rotate, Point::lerp, Point::angle
+
+    let turned = Point { x: 1.0, y: 0.0 }.rotate(std::f64::consts::FRAC_PI_2);
+    assert!(turned.distance(&Point { x: 0.0, y: 1.0 }) < 1e-12);
+    assert!((turned.angle() - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+
+    let a = Point { x: 0.0, y: 0.0 };
+    let b = Point { x: 10.0, y: -4.0 };
+    assert_eq!(a.lerp(&b, 0.5), Point { x: 5.0, y: -2.0 });
+    assert_eq!(a.lerp(&b, 2.0), Point { x: 20.0, y: -8.0 });
+
+    // Writing Point { x, y } everywhere gets tedious, so, as with Bounds, we provide conversions to and from a tuple. For printing, Display rounds each coordinate to six decimal places and then uses f64's shortest representation, so 0.1 + 0.2 shows as 0.3 rather than 0.30000000000000004. Past a billion or so, scaling by a million would lose digits, or overflow to infinity, and there's nothing below the sixth decimal place left to round anyway, so large coordinates print unchanged. If the caller asks for a precision, as in {:.2}, we honor it instead:
+    // In src/point.rs:
+    //     impl From<(f64, f64)> for Point
+    //     impl From<Point> for (f64, f64)
+    //     impl fmt::Display for Point
+
+    let p = Point::from((1.5, -2.0));
+    assert_eq!(p, Point { x: 1.5, y: -2.0 });
+    assert_eq!(<(f64, f64)>::from(p), (1.5, -2.0));
+    assert_eq!(p.to_string(), "(1.5, -2.0)");
+    assert_eq!(Point::from((0.1 + 0.2, 1.0 / 3.0)).to_string(), "(0.3, 0.333333)");
+    assert_eq!(Point { x: 1e303, y: 0.0 }.to_string(), "(1e303, 0.0)");
+    assert_eq!(Point { x: -2.5e10, y: 0.25 }.to_string(), "(-25000000000.0, 0.25)");
+    assert_eq!(format!("{:.2}", p), "(1.50, -2.00)");
+
+
+
+    // Point3, a 3-D Companion
+
+    // Broom positions are 3-D, as (f32, f32, f32) tuples. Point3 gives them the same vector operations Point has, plus the cross product, which only exists in three dimensions: it's perpendicular to both its operands, following the right-hand rule. Conversions from and to the tuple type let Point3 work directly with Broom::position; the conversion to f32 loses precision, as narrowing always does. extend and truncate move between two and three dimensions by adding or dropping z:
+    // In src/point.rs:
+    //     Point3
+    //     impl Add for Point3
+    //     impl Sub for Point3
+    //     impl Neg for Point3
+    //     impl Mul<f64> for Point3
+    //     impl Div<f64> for Point3
+    //     impl AddAssign for Point3
+    //     impl SubAssign for Point3
+    //     Point3::dot, Point3::cross, Point3::magnitude, Point3::distance, Point3::truncate
+    //     Point::extend
+    //     impl From<(f32, f32, f32)> for Point3
+    //     impl From<Point3> for (f32, f32, f32)
+
+    let i = Point3 { x: 1.0, y: 0.0, z: 0.0 };
+    let j = Point3 { x: 0.0, y: 1.0, z: 0.0 };
+    let k = Point3 { x: 0.0, y: 0.0, z: 1.0 };
+    assert_eq!(i.cross(&j), k);
+    assert_eq!(j.cross(&k), i);
+    assert_eq!(k.cross(&i), j);
+    assert_eq!(j.cross(&i), -k);
+    assert_eq!(i.cross(&i), Point3::ORIGIN);
+
+    let a = Point3 { x: 1.0, y: 2.0, z: 2.0 };
+    assert_eq!(a.magnitude(), 3.0);
+    assert_eq!(a.distance(&(a * 2.0)), 3.0);
+    assert_eq!((a + a - a) / 1.0, a);
+    assert_eq!(a.dot(&i.cross(&j)), 2.0);
+
+    let mut hokey = Broom::builder().name("Hokey").position((3.0, 4.0, 0.0)).build();
+    let mut pos = Point3::from(hokey.position);
+    pos += Point3 { x: 0.0, y: 0.0, z: 12.0 };
+    hokey.position = pos.into();
+    assert_eq!(hokey.position, (3.0, 4.0, 12.0));
+
+    assert_eq!(Point { x: 1.5, y: -2.0 }.extend(7.0).truncate(), Point { x: 1.5, y: -2.0 });
+
+
+
+    // Saving Structs with Serde
+
+    // To save our structs as JSON and load them back, we can have the serde crate derive its Serialize and Deserialize traits for us. Since not every user of this code wants the dependency, the derives sit behind an optional serde Cargo feature, enabled with cargo run --features serde. The #[cfg_attr(feature = "serde", ...)] attributes on the definitions of GrayscaleMap, Broom, BroomIntent, Bounds, and Point apply the derive only when the feature is on.
+
+    // Deriving Deserialize for GrayscaleMap directly would let a hand-edited file with too few pixels for its size slip past the check GrayscaleMap::new makes. So GrayscaleMap's definition also carries serde(try_from = "UncheckedGrayscaleMap"): serde deserializes this look-alike struct, which has no invariant, and then converts it with TryFrom, turning a GrayscaleMapError into a deserialization error:
+    // In src/grayscale.rs:
+    //     UncheckedGrayscaleMap
+    //     impl TryFrom<UncheckedGrayscaleMap> for GrayscaleMap
+
+    #[cfg(feature = "serde")]
+    {
+        let p = Point { x: 1.5, y: -2.0 };
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(json, r#"{"x":1.5,"y":-2.0}"#);
+        assert_eq!(serde_json::from_str::<Point>(&json).unwrap(), p);
+
+        let b = Bounds(1024, 768);
+        let json = serde_json::to_string(&b).unwrap();
+        assert_eq!(json, "[1024,768]");
+        assert_eq!(serde_json::from_str::<Bounds>(&json).unwrap(), b);
+
+        let map = GrayscaleMap::new((2, 1), vec![7, 9]).unwrap();
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(serde_json::from_str::<GrayscaleMap>(&json).unwrap(), map);
+        assert!(serde_json::from_str::<GrayscaleMap>(r#"{"pixels":[7],"size":[2,1]}"#).is_err());
+    }
+
+
+
+    // Default Values
+
+    // Default supplies a type's "empty" value, which #[derive(Default)] on a containing struct and std::mem::take both rely on. For Point, Bounds, and GrayscaleMap, the derived impl, which defaults each field, already gives the right answer: the origin, a zero size, and an empty image whose buffer length agrees with its size. For an enum, derive(Default) needs to be told which variant to use, so BroomIntent marks FetchWater with #[default]. Queue<T> got a hand-written Default earlier, since deriving it would demand T: Default for no reason:
+    assert_eq!(Point::default(), Point { x: 0.0, y: 0.0 });
+    assert_eq!(Point::default(), Point::ORIGIN);
+    assert_eq!(Bounds::default(), Bounds(0, 0));
+    assert!(matches!(BroomIntent::default(), BroomIntent::FetchWater));
+    assert!(Queue::<String>::default().is_empty());
+
+    let mut frame = GrayscaleMap::new((2, 2), vec![1, 2, 3, 4]).unwrap();
+    let taken = std::mem::take(&mut frame);
+    assert_eq!(taken.size, (2, 2));
+    assert_eq!(frame, GrayscaleMap::default());
+    assert_eq!((frame.size, frame.pixels.len()), ((0, 0), 0));
+
+
+
+    // Hashing Bounds and BroomIntent
+
+    // To use a type as a HashMap key or HashSet element, it must implement Eq and Hash, and equal values must hash equally. Bounds, being two usizes, and BroomIntent, a fieldless enum, can simply derive both. Point deliberately doesn't: f64 implements neither Eq nor Hash, because NaN isn't equal to itself and 0.0 == -0.0 even though their bits differ, so no hash of the raw fields could agree with ==. Code that needs points as keys should decide for itself how to quantize them, say to integer grid cells:
+    use std::collections::{HashMap, HashSet};
+
+    let mut sizes = HashSet::new();
+    sizes.insert(Bounds(1024, 768));
+    sizes.insert(Bounds(640, 480));
+    sizes.insert(Bounds(1024, 768));
+    sizes.insert(Bounds::from((640, 480)));
+    assert_eq!(sizes.len(), 2);
+    assert!(sizes.contains(&Bounds(640, 480)));
+
+    let mut trips = HashMap::new();
+    for intent in [BroomIntent::FetchWater, BroomIntent::DumpWater, BroomIntent::FetchWater] {
+        *trips.entry(intent).or_insert(0) += 1;
+    }
+    assert_eq!(trips[&BroomIntent::FetchWater], 2);
+    assert_eq!(trips[&BroomIntent::DumpWater], 1);
+
+
+
+    // The Core Types Without std
+
+    // The library, src/lib.rs, is marked #![no_std], so the types can go where there's no operating system. Point, Point3, Bounds, BroomIntent, Extrema, and Luminance use only core. Queue, GrayscaleMap, Grid, and parsing a BroomIntent, whose InvalidIntent error keeps a copy of the bad input in a String, need a heap, and are only compiled with the alloc feature. core's f64 has no hypot, sin_cos, atan2, sqrt, or round, so Point's distance, rotate, angle, and Display, and Bounds::scale, are compiled only with the std feature, which is on by default, or with libm for targets without an operating system. The image-processing methods that do floating-point math or file I/O, and the std::error::Error impls, need std itself. This notes binary uses the whole library, so Cargo.toml gives it required-features = ["std"], and Cargo skips it rather than failing when std is off. tests/no_std.rs is itself a #![no_std] crate that uses the core types, so cargo test --no-default-features --test no_std checks that they still build without std or alloc.
+
+
+
     // Interior Mutability
 
     // Mutability is like anything else. In excess, it causes problems, but we often want just a little bit of it. For example, say our spider robot control system has a central struct, SpiderRobot, that contains settings and I/O handles. It's set up when the robot boots, and the values never change:
@@ -0,0 +1,27 @@
+//! The floating-point functions `core` lacks, taken from `std` when it's
+//! available and from `libm` otherwise.
+
+#[cfg(feature = "std")]
+pub fn hypot(x: f64, y: f64) -> f64 { x.hypot(y) }
+#[cfg(not(feature = "std"))]
+pub fn hypot(x: f64, y: f64) -> f64 { libm::hypot(x, y) }
+
+#[cfg(feature = "std")]
+pub fn atan2(y: f64, x: f64) -> f64 { y.atan2(x) }
+#[cfg(not(feature = "std"))]
+pub fn atan2(y: f64, x: f64) -> f64 { libm::atan2(y, x) }
+
+#[cfg(feature = "std")]
+pub fn sin_cos(x: f64) -> (f64, f64) { x.sin_cos() }
+#[cfg(not(feature = "std"))]
+pub fn sin_cos(x: f64) -> (f64, f64) { libm::sincos(x) }
+
+#[cfg(feature = "std")]
+pub fn round(x: f64) -> f64 { x.round() }
+#[cfg(not(feature = "std"))]
+pub fn round(x: f64) -> f64 { libm::round(x) }
+
+#[cfg(feature = "std")]
+pub fn sqrt(x: f64) -> f64 { x.sqrt() }
+#[cfg(not(feature = "std"))]
+pub fn sqrt(x: f64) -> f64 { libm::sqrt(x) }
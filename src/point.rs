@@ -0,0 +1,251 @@
+#[cfg(any(feature = "std", feature = "libm"))]
+use core::fmt;
+use core::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+#[cfg(any(feature = "std", feature = "libm"))]
+use crate::math;
+
+/// A point, or vector, in the plane.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point {
+    pub x: f64,
+    pub y: f64
+}
+
+impl Point {
+    /// The point `(0, 0)`.
+    pub const ORIGIN: Point = Point { x: 0.0, y: 0.0 };
+}
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl Point {
+    /// Return the distance between `self` and `other`.
+    pub fn distance(&self, other: &Point) -> f64 {
+        math::hypot(self.x - other.x, self.y - other.y)
+    }
+
+    /// Return the distance from the origin to `self`.
+    pub fn magnitude(&self) -> f64 {
+        self.distance(&Point::ORIGIN)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+    fn add(self, other: Point) -> Point {
+        Point { x: self.x + other.x, y: self.y + other.y }
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+    fn sub(self, other: Point) -> Point {
+        Point { x: self.x - other.x, y: self.y - other.y }
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+    fn neg(self) -> Point {
+        Point { x: -self.x, y: -self.y }
+    }
+}
+
+impl Mul<f64> for Point {
+    type Output = Point;
+    fn mul(self, k: f64) -> Point {
+        Point { x: self.x * k, y: self.y * k }
+    }
+}
+
+impl Div<f64> for Point {
+    type Output = Point;
+    fn div(self, k: f64) -> Point {
+        Point { x: self.x / k, y: self.y / k }
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other: Point) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, other: Point) {
+        *self = *self - other;
+    }
+}
+
+impl Point {
+    /// Return the dot product of `self` and `other`.
+    pub fn dot(&self, other: &Point) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Return the point a fraction `t` of the way from `self` to `other`.
+    /// `t` is not clamped.
+    pub fn lerp(&self, other: &Point, t: f64) -> Point {
+        *self * (1.0 - t) + *other * t
+    }
+}
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl Point {
+    /// Return `self` rotated counterclockwise about the origin by `radians`.
+    pub fn rotate(&self, radians: f64) -> Point {
+        let (sin, cos) = math::sin_cos(radians);
+        Point {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+
+    /// Return the angle of `self` from the positive x axis, in radians.
+    pub fn angle(&self) -> f64 {
+        math::atan2(self.y, self.x)
+    }
+}
+
+impl From<(f64, f64)> for Point {
+    fn from((x, y): (f64, f64)) -> Point {
+        Point { x, y }
+    }
+}
+
+impl From<Point> for (f64, f64) {
+    fn from(p: Point) -> (f64, f64) {
+        (p.x, p.y)
+    }
+}
+
+/// Prints `(x, y)`, with each coordinate rounded to six decimal places, or
+/// to the formatter's precision if one is given. Coordinates too large for
+/// the rounding to matter print unchanged.
+#[cfg(any(feature = "std", feature = "libm"))]
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match f.precision() {
+            Some(places) => write!(f, "({:.*}, {:.*})", places, self.x, places, self.y),
+            None => {
+                let round = |v: f64| {
+                    let scaled = v * 1e6;
+                    if scaled.is_finite() && v.abs() < 1e9 { math::round(scaled) / 1e6 } else { v }
+                };
+                write!(f, "({:?}, {:?})", round(self.x), round(self.y))
+            }
+        }
+    }
+}
+
+/// A point, or vector, in space.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Point3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64
+}
+
+impl Add for Point3 {
+    type Output = Point3;
+    fn add(self, other: Point3) -> Point3 {
+        Point3 { x: self.x + other.x, y: self.y + other.y, z: self.z + other.z }
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Point3;
+    fn sub(self, other: Point3) -> Point3 {
+        Point3 { x: self.x - other.x, y: self.y - other.y, z: self.z - other.z }
+    }
+}
+
+impl Neg for Point3 {
+    type Output = Point3;
+    fn neg(self) -> Point3 {
+        Point3 { x: -self.x, y: -self.y, z: -self.z }
+    }
+}
+
+impl Mul<f64> for Point3 {
+    type Output = Point3;
+    fn mul(self, k: f64) -> Point3 {
+        Point3 { x: self.x * k, y: self.y * k, z: self.z * k }
+    }
+}
+
+impl Div<f64> for Point3 {
+    type Output = Point3;
+    fn div(self, k: f64) -> Point3 {
+        Point3 { x: self.x / k, y: self.y / k, z: self.z / k }
+    }
+}
+
+impl AddAssign for Point3 {
+    fn add_assign(&mut self, other: Point3) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Point3 {
+    fn sub_assign(&mut self, other: Point3) {
+        *self = *self - other;
+    }
+}
+
+impl Point3 {
+    /// The point `(0, 0, 0)`.
+    pub const ORIGIN: Point3 = Point3 { x: 0.0, y: 0.0, z: 0.0 };
+
+    /// Return the dot product of `self` and `other`.
+    pub fn dot(&self, other: &Point3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Return the cross product `self` x `other`.
+    pub fn cross(&self, other: &Point3) -> Point3 {
+        Point3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x
+        }
+    }
+
+    /// Return the 2-D point `(x, y)`, dropping `z`.
+    pub fn truncate(&self) -> Point {
+        Point { x: self.x, y: self.y }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl Point3 {
+    /// Return the distance from the origin to `self`.
+    pub fn magnitude(&self) -> f64 {
+        math::sqrt(self.dot(self))
+    }
+
+    /// Return the distance between `self` and `other`.
+    pub fn distance(&self, other: &Point3) -> f64 {
+        (*self - *other).magnitude()
+    }
+}
+
+impl Point {
+    /// Return the 3-D point `(x, y, z)`.
+    pub fn extend(&self, z: f64) -> Point3 {
+        Point3 { x: self.x, y: self.y, z }
+    }
+}
+
+impl From<(f32, f32, f32)> for Point3 {
+    fn from((x, y, z): (f32, f32, f32)) -> Point3 {
+        Point3 { x: x as f64, y: y as f64, z: z as f64 }
+    }
+}
+
+impl From<Point3> for (f32, f32, f32) {
+    fn from(p: Point3) -> (f32, f32, f32) {
+        (p.x as f32, p.y as f32, p.z as f32)
+    }
+}
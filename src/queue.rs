@@ -0,0 +1,474 @@
+use alloc::vec::Vec;
+use core::iter::{FromIterator, Rev};
+
+/// A first-in, first-out queue.
+#[derive(Clone, Debug)]
+pub struct Queue<T> {
+    older: Vec<T>,         // older elements, eldest last.
+    younger: Vec<T>,       // younger elements, youngest last.
+    max_len: Option<usize> // the most elements try_push will allow, if any
+}
+
+impl<T> Queue<T> {
+    pub fn new() -> Queue<T> {
+        Queue { older: Vec::new(), younger: Vec::new(), max_len: None }
+    }
+
+    /// Push `t` onto the back of the queue.
+    pub fn push(&mut self, t: T) {
+        self.younger.push(t);
+    }
+
+    /// Pop the element at the front of the queue, or return `None` if the
+    /// queue is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.older.is_empty() {
+            // Bring the elements in younger over to older, and put them in
+            // the promised order.
+            core::mem::swap(&mut self.older, &mut self.younger);
+            self.older.reverse();
+        }
+        self.older.pop()
+    }
+
+    /// Consume the queue, returning its two vectors: the elements that will
+    /// pop first, eldest last, and the rest in push order.
+    pub fn split(self) -> (Vec<T>, Vec<T>) {
+        (self.older, self.younger)
+    }
+
+    /// Return the number of elements in the queue.
+    pub fn len(&self) -> usize {
+        self.older.len() + self.younger.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Queue<T> {
+    /// Fold over the queue's elements in pop order, without removing them.
+    /// Stop at the first `Err` returned by `f` and return it.
+    pub fn try_fold<B, E, F>(&self, init: B, f: F) -> Result<B, E>
+        where F: FnMut(B, &T) -> Result<B, E>
+    {
+        self.iter().try_fold(init, f)
+    }
+}
+
+impl<T> Queue<T> {
+    /// Remove up to `n` of the most recently pushed elements, and return
+    /// them most-recent first. Return fewer than `n` if the queue runs out.
+    pub fn pop_back_n(&mut self, n: usize) -> Vec<T> {
+        let mut popped = Vec::with_capacity(n.min(self.len()));
+        while popped.len() < n {
+            match self.pop_back() {
+                Some(t) => popped.push(t),
+                None => break
+            }
+        }
+
+        popped
+    }
+}
+
+impl<T: Ord> Queue<T> {
+    /// Consume the queue, returning all of its elements in sorted order.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let (mut older, mut younger) = self.split();
+        older.append(&mut younger);
+        older.sort();
+        older
+    }
+}
+
+impl<T: PartialEq + Clone> Queue<T> {
+    /// Replace every element equal to `target` with a clone of
+    /// `replacement`, leaving the order of the queue unchanged.
+    pub fn replace_all(&mut self, target: &T, replacement: T) {
+        for elem in self.older.iter_mut().chain(self.younger.iter_mut()) {
+            if *elem == *target {
+                *elem = replacement.clone();
+            }
+        }
+    }
+}
+
+impl<T> Queue<T> {
+    /// Remove the elements at pop-order positions `start..end` and return
+    /// them in pop order. Panic if `start > end` or `end` is past the end
+    /// of the queue.
+    pub fn drain_range(&mut self, start: usize, end: usize) -> Vec<T> {
+        let older_len = self.older.len();
+        let len = older_len + self.younger.len();
+        if start > end {
+            panic!("queue range starts at {} but ends at {}", start, end);
+        }
+        if end > len {
+            panic!("range end index {} out of range for queue of length {}", end, len);
+        }
+
+        let mut drained: Vec<T> = Vec::with_capacity(end - start);
+        if start < older_len {
+            let lo = older_len - end.min(older_len);
+            let hi = older_len - start;
+            drained.extend(self.older.drain(lo..hi).rev());
+        }
+        if end > older_len {
+            let lo = start.max(older_len) - older_len;
+            let hi = end - older_len;
+            drained.extend(self.younger.drain(lo..hi));
+        }
+
+        drained
+    }
+}
+
+impl<T> Queue<T> {
+    /// Return a mutable reference to the element at the front of the
+    /// queue, or `None` if the queue is empty.
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        if self.older.is_empty() {
+            core::mem::swap(&mut self.older, &mut self.younger);
+            self.older.reverse();
+        }
+
+        self.older.last_mut()
+    }
+}
+
+impl<T> Queue<T> {
+    /// Return an iterator over each consecutive pair `(prev, next)` of
+    /// elements in pop order.
+    pub fn pairs(&self) -> impl Iterator<Item = (&T, &T)> {
+        self.iter().zip(self.iter().skip(1))
+    }
+}
+
+impl<T> Queue<T> {
+    /// Move the first element in pop order satisfying `pred` to the front
+    /// of the queue, leaving the others in their original order. Return
+    /// `false` if no element matched.
+    pub fn promote<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> bool {
+        let found = if let Some(i) = self.older.iter().rposition(&mut pred) {
+            self.older.remove(i)
+        } else if let Some(i) = self.younger.iter().position(&mut pred) {
+            self.younger.remove(i)
+        } else {
+            return false;
+        };
+
+        self.older.push(found);
+        true
+    }
+}
+
+/// An iterator over references to a `Queue`'s elements, in pop order.
+pub struct Iter<'a, T> {
+    older: Rev<core::slice::Iter<'a, T>>,
+    younger: core::slice::Iter<'a, T>
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.older.next().or_else(|| self.younger.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.older.len() + self.younger.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+/// An iterator that moves a `Queue`'s elements out, in pop order.
+pub struct IntoIter<T> {
+    older: Rev<alloc::vec::IntoIter<T>>,
+    younger: alloc::vec::IntoIter<T>
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.older.next().or_else(|| self.younger.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.older.len() + self.younger.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> Queue<T> {
+    /// Return an iterator over the queue's elements in pop order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { older: self.older.iter().rev(), younger: self.younger.iter() }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Queue<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for Queue<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { older: self.older.into_iter().rev(), younger: self.younger.into_iter() }
+    }
+}
+
+impl<T> Queue<T> {
+    /// If the queue is non-empty, return its front element and a queue of
+    /// the remaining elements. Otherwise, return `None`.
+    pub fn split_first(mut self) -> Option<(T, Queue<T>)> {
+        let head = self.pop()?;
+        Some((head, self))
+    }
+}
+
+impl<T> Queue<T> {
+    /// Keep only the elements for which `f(index, element)` returns true,
+    /// where `index` is the element's position in pop order. `f` is
+    /// called on the elements in pop order.
+    pub fn retain_indexed<F: FnMut(usize, &T) -> bool>(&mut self, mut f: F) {
+        let mut index = 0;
+        let mut keep = |t: &T| {
+            index += 1;
+            f(index - 1, t)
+        };
+
+        self.older.reverse();
+        self.older.retain(&mut keep);
+        self.older.reverse();
+        self.younger.retain(keep);
+    }
+}
+
+impl<T> Queue<T> {
+    /// Return a reference to the element `pop` would return next, or `None`
+    /// if the queue is empty.
+    pub fn peek(&self) -> Option<&T> {
+        self.older.last().or_else(|| self.younger.first())
+    }
+}
+
+impl<T> FromIterator<T> for Queue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut q = Queue::new();
+        q.extend(iter);
+        q
+    }
+}
+
+impl<T> Extend<T> for Queue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.younger.extend(iter);
+    }
+}
+
+impl<T> Queue<T> {
+    /// Remove and return the most recently pushed element, or `None` if
+    /// the queue is empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.younger.is_empty() {
+            if self.older.is_empty() {
+                return None;
+            }
+
+            // Bring the younger half of older over to younger, youngest
+            // last. The elder half stays put, so that a following pop
+            // doesn't have to move everything straight back again.
+            let half = self.older.len().div_ceil(2);
+            self.younger.extend(self.older.drain(..half).rev());
+        }
+
+        self.younger.pop()
+    }
+
+    /// Return a reference to the element `pop_back` would return next, or
+    /// `None` if the queue is empty.
+    pub fn peek_back(&self) -> Option<&T> {
+        self.younger.last().or_else(|| self.older.first())
+    }
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Queue::new()
+    }
+}
+
+impl<T: PartialEq> PartialEq for Queue<T> {
+    fn eq(&self, other: &Queue<T>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Queue<T> {}
+
+impl<T> Queue<T> {
+    /// Create an empty queue with room for `n` elements, split between its
+    /// two internal vectors.
+    pub fn with_capacity(n: usize) -> Queue<T> {
+        Queue {
+            older: Vec::with_capacity(n / 2),
+            younger: Vec::with_capacity(n - n / 2),
+            max_len: None
+        }
+    }
+
+    /// Return the number of elements the queue's two vectors can hold
+    /// between them without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.older.capacity() + self.younger.capacity()
+    }
+
+    /// Make room for at least `additional` more pushes without
+    /// reallocating.
+    pub fn reserve(&mut self, additional: usize) {
+        self.younger.reserve(additional);
+    }
+
+    /// Release as much unused capacity as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.older.shrink_to_fit();
+        self.younger.shrink_to_fit();
+    }
+}
+
+impl<T> Queue<T> {
+    /// Remove all elements, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.older.clear();
+        self.younger.clear();
+    }
+
+    /// Move all of `other`'s elements onto the back of `self`, in pop
+    /// order, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Queue<T>) {
+        self.younger.extend(other.older.drain(..).rev());
+        self.younger.append(&mut other.younger);
+    }
+}
+
+impl<T: PartialEq> Queue<T> {
+    /// Return true if the queue holds an element equal to `value`.
+    pub fn contains(&self, value: &T) -> bool {
+        self.older.contains(value) || self.younger.contains(value)
+    }
+}
+
+impl<T> Queue<T> {
+    /// Set the most elements `try_push` will allow the queue to hold, or
+    /// remove the limit with `None`.
+    pub fn set_max_len(&mut self, max: Option<usize>) {
+        self.max_len = max;
+    }
+
+    /// Push `t` onto the back of the queue, unless the queue already holds
+    /// its maximum number of elements, in which case return `Err(t)`.
+    pub fn try_push(&mut self, t: T) -> Result<(), T> {
+        match self.max_len {
+            Some(max) if self.len() >= max => Err(t),
+            _ => {
+                self.push(t);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<T> Queue<T> {
+    /// Keep only the elements for which `f` returns true, preserving
+    /// their order. `f` is called on the elements in pop order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_indexed(|_, t| f(t));
+    }
+
+    /// Remove the elements for which `f` returns true, and return them in
+    /// pop order. `f` is called on the elements in pop order.
+    pub fn drain_filter<F: FnMut(&T) -> bool>(&mut self, mut f: F) -> Vec<T> {
+        let older = core::mem::take(&mut self.older);
+        let younger = core::mem::take(&mut self.younger);
+
+        let mut removed = Vec::new();
+        for t in older.into_iter().rev().chain(younger) {
+            if f(&t) {
+                removed.push(t);
+            } else {
+                self.younger.push(t);
+            }
+        }
+        removed
+    }
+}
+
+impl<T> Queue<T> {
+    /// Return a queue holding `f` applied to each element, in the same
+    /// order. `f` is called on the elements in pop order.
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> Queue<U> {
+        let max_len = self.max_len;
+        Queue { older: Vec::new(), younger: self.into_iter().map(f).collect(), max_len }
+    }
+}
+
+impl<T> Queue<T> {
+    /// Remove the first `n` elements, in pop order, and return them as a
+    /// new queue. If `n` is at least the length, move everything.
+    pub fn split_off(&mut self, n: usize) -> Queue<T> {
+        if n <= self.older.len() {
+            let older = self.older.split_off(self.older.len() - n);
+            return Queue { older, younger: Vec::new(), max_len: None };
+        }
+
+        let from_younger = (n - self.older.len()).min(self.younger.len());
+        let rest = self.younger.split_off(from_younger);
+        Queue {
+            older: core::mem::take(&mut self.older),
+            younger: core::mem::replace(&mut self.younger, rest),
+            max_len: None
+        }
+    }
+}
+
+impl<T> Queue<T> {
+    /// Rearrange the elements so they occupy a single slice, and return
+    /// that slice, in pop order.
+    pub fn make_contiguous(&mut self) -> &[T] {
+        if !self.older.is_empty() {
+            self.older.reverse();
+            self.older.append(&mut self.younger);
+            core::mem::swap(&mut self.older, &mut self.younger);
+        }
+        &self.younger
+    }
+}
+
+impl<T: Into<f64> + Copy> Queue<T> {
+    /// Return the sum of the elements, or 0.0 if there are none.
+    pub fn sum(&self) -> f64 {
+        self.iter().map(|&t| t.into()).sum()
+    }
+
+    /// Return the mean of the elements, or `None` if there are none.
+    pub fn average(&self) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.sum() / self.len() as f64)
+    }
+}
@@ -0,0 +1,40 @@
+// Build the crate's core types with none of its features, and use them from a
+// crate that doesn't link std itself. If anything in them reached for std,
+// `cargo test --no-default-features --test no_std` would fail to compile.
+#![no_std]
+
+use structs::{find_extrema, find_extrema_indexed, Bounds, BroomIntent, Luminance, Point, Point3};
+
+#[test]
+fn core_types_build_without_std() {
+    let p = Point { x: 1.5, y: -2.0 } + Point::from((0.5, 2.0));
+    assert_eq!(p, Point { x: 2.0, y: 0.0 });
+    assert_eq!(p.dot(&Point { x: 3.0, y: 7.0 }), 6.0);
+    assert_eq!(Point::ORIGIN.lerp(&p, 0.5), Point { x: 1.0, y: 0.0 });
+
+    let mut rects = [Bounds(2, 3), Bounds(1, 1), Bounds(3, 2)];
+    rects.sort();
+    assert_eq!(rects, [Bounds(1, 1), Bounds(2, 3), Bounds(3, 2)]);
+    assert_eq!(Bounds(usize::MAX, 2).area(), None);
+    assert!(Bounds(4, 3).contains(3, 2));
+
+    assert_eq!(BroomIntent::default(), BroomIntent::FetchWater);
+    assert_ne!(BroomIntent::FetchWater, BroomIntent::DumpWater);
+
+    let mut l = Luminance(250) + 10;
+    assert_eq!(l, Luminance(255));
+    l = l - 5;
+    assert_eq!(l.0, 250);
+
+    let a = Point3 { x: 1.0, y: 0.0, z: 0.0 };
+    let b = Point { x: 0.0, y: 1.0 }.extend(0.0);
+    assert_eq!(a.cross(&b), Point3 { x: 0.0, y: 0.0, z: 1.0 });
+    assert_eq!((a + b).truncate(), Point { x: 1.0, y: 1.0 });
+
+    let e = find_extrema(&[-3, 0, 15, 48]).unwrap();
+    assert_eq!(e.range(), 51);
+    assert!(e.contains(&0) && !e.contains(&49));
+    let (_, greatest_index, least_index) = find_extrema_indexed(&[7, 48, -3, 48]).unwrap();
+    assert_eq!((greatest_index, least_index), (1, 2));
+    assert!(find_extrema::<u8>(&[]).is_none());
+}